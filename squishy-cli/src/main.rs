@@ -144,7 +144,7 @@ fn main() {
                 None
             };
 
-            let offset = offset.unwrap_or(get_offset(&file).unwrap());
+            let offset = offset.unwrap_or_else(|| get_offset(&file).unwrap());
             let squashfs = SquashFS::from_path_with_offset(&file, offset)
                 .map_err(|_| {
                     SquishyError::InvalidSquashFS(
@@ -193,12 +193,12 @@ fn main() {
                                 output_path.display()
                             );
                         }
-                        EntryKind::Symlink(e) => {
-                            if output_path.exists() {
+                        EntryKind::Symlink(target) => {
+                            // Dangling symlinks don't `exists()`, but are still in the way
+                            if output_path.symlink_metadata().is_ok() {
                                 return;
                             }
-                            let original_path = e.strip_prefix("/").unwrap_or(&e);
-                            let _ = unix::fs::symlink(original_path, &output_path);
+                            let _ = unix::fs::symlink(&target, &output_path);
                             log!(
                                 args.quiet,
                                 "Wrote {} to {}",
@@ -64,10 +64,13 @@ impl<'a> AppImage<'a> {
     /// * `offset` - Offset to seek to
     pub fn new<P: AsRef<Path>>(
         filter: Option<&'a str>,
-        path: &'a P,
+        path: P,
         offset: Option<u64>,
     ) -> Result<Self> {
-        let offset = offset.unwrap_or(get_offset(path)?);
+        let offset = match offset {
+            Some(offset) => offset,
+            None => get_offset(&path)?,
+        };
         let squashfs = SquashFS::from_path_with_offset(path, offset).map_err(|_| {
             SquishyError::InvalidSquashFS(
                 "Couldn't find squashfs. Try providing valid offset.".to_owned(),
@@ -86,7 +89,7 @@ impl<'a> AppImage<'a> {
     ///
     /// # Returns
     /// A SquashFS entry to the icon, if found
-    pub fn find_icon(&self) -> Option<SquashFSEntry> {
+    pub fn find_icon(&self) -> Option<SquashFSEntry<'_>> {
         let icon = self
             .search_diricon()
             .or_else(|| self.find_largest_icon_path())
@@ -106,7 +109,7 @@ impl<'a> AppImage<'a> {
     ///
     /// # Returns
     /// A SquashFS entry to the icon, if found
-    fn search_diricon(&self) -> Option<SquashFSEntry> {
+    fn search_diricon(&self) -> Option<SquashFSEntry<'_>> {
         self.squashfs
             .par_entries()
             .find_first(|entry| entry.path.to_string_lossy() == "/.DirIcon")
@@ -119,14 +122,14 @@ impl<'a> AppImage<'a> {
     fn filter_path(&self, path: &str) -> bool {
         self.filter
             .as_ref()
-            .map_or(true, |filter| path.contains(filter))
+            .is_none_or(|filter| path.contains(filter))
     }
 
     /// Find largest png (preferred) or svg icon in /usr/share/icons, filtered
     ///
     /// # Returns
     /// A SquashFS entry to the icon, if found
-    fn find_largest_icon_path(&self) -> Option<SquashFSEntry> {
+    fn find_largest_icon_path(&self) -> Option<SquashFSEntry<'_>> {
         let png_entries = self.squashfs.par_entries().filter(|entry| {
             let path = entry.path.to_string_lossy().to_lowercase();
             path.starts_with("/usr/share/icons/")
@@ -150,7 +153,7 @@ impl<'a> AppImage<'a> {
     ///
     /// # Returns
     /// A SquashFS entry to the icon, if found
-    fn find_png_icon(&self) -> Option<SquashFSEntry> {
+    fn find_png_icon(&self) -> Option<SquashFSEntry<'_>> {
         let png_entries = self.squashfs.par_entries().filter(|entry| {
             let p = entry.path.to_string_lossy().to_lowercase();
             self.filter_path(&p) && p.ends_with(".png")
@@ -165,7 +168,7 @@ impl<'a> AppImage<'a> {
     ///
     /// # Returns
     /// A SquashFS entry to the icon, if found
-    fn find_svg_icon(&self) -> Option<SquashFSEntry> {
+    fn find_svg_icon(&self) -> Option<SquashFSEntry<'_>> {
         self.squashfs.par_entries().find_first(|entry| {
             let path = entry.path.to_string_lossy().to_lowercase();
             self.filter_path(&path) && path.ends_with(".svg")
@@ -176,7 +179,7 @@ impl<'a> AppImage<'a> {
     ///
     /// # Returns
     /// A SquashFS entry to the desktop file, if found
-    pub fn find_desktop(&self) -> Option<SquashFSEntry> {
+    pub fn find_desktop(&self) -> Option<SquashFSEntry<'_>> {
         let desktop = self.squashfs.par_entries().find_first(|entry| {
             let path = entry.path.to_string_lossy().to_lowercase();
             self.filter_path(&path) && path.ends_with(".desktop")
@@ -195,7 +198,7 @@ impl<'a> AppImage<'a> {
     ///
     /// # Returns
     /// A SquashFS entry to the appstream, if found
-    pub fn find_appstream(&self) -> Option<SquashFSEntry> {
+    pub fn find_appstream(&self) -> Option<SquashFSEntry<'_>> {
         let appstream = self.squashfs.par_entries().find_first(|entry| {
             let path = entry.path.to_string_lossy().to_lowercase();
             self.filter_path(&path)
@@ -210,4 +213,259 @@ impl<'a> AppImage<'a> {
         }
         appstream
     }
+
+    /// Find the desktop entry at the root of the AppImage, as required by the
+    /// AppImage spec. Symlinks are resolved.
+    ///
+    /// # Returns
+    /// A SquashFS entry to the desktop file, if found
+    pub fn find_desktop_entry(&self) -> Result<Option<SquashFSEntry<'_>>> {
+        let desktop = self.squashfs.find_first(|path| {
+            path.parent() == Some(Path::new("/"))
+                && path.extension().is_some_and(|ext| ext == "desktop")
+        });
+        match desktop {
+            Some(desktop) => self.resolve(desktop),
+            None => Ok(None),
+        }
+    }
+
+    /// Find the AppImage icon following the AppImage spec. Symlinks are resolved.
+    /// It looks for icon in order:
+    /// - DirIcon at AppImage root
+    /// - Icon named by the `Icon` key of the root desktop entry, at AppImage root
+    /// - Icon named by the `Icon` key in /usr/share/icons or /usr/share/pixmaps,
+    ///   preferring the largest png, then svg, then xpm
+    ///
+    /// # Returns
+    /// A SquashFS entry to the icon, if found
+    pub fn find_icon_entry(&self) -> Result<Option<SquashFSEntry<'_>>> {
+        if let Some(diricon) = self.squashfs.get_entry("/.DirIcon") {
+            if let Some(icon) = self.resolve(diricon)? {
+                return Ok(Some(icon));
+            }
+        }
+
+        let Some(desktop) = self.find_desktop_entry()? else {
+            return Ok(None);
+        };
+        let Some(name) = desktop_icon_name(&self.squashfs.read_file(&desktop.path)?) else {
+            return Ok(None);
+        };
+
+        if name.starts_with('/') {
+            return match self.squashfs.get_entry(&name) {
+                Some(icon) => self.resolve(icon),
+                None => Ok(None),
+            };
+        }
+
+        for ext in ICON_EXTENSIONS {
+            if let Some(icon) = self.squashfs.get_entry(format!("/{name}.{ext}")) {
+                return self.resolve(icon);
+            }
+        }
+
+        let icon = self
+            .squashfs
+            .entries()
+            .filter(|entry| {
+                (entry.path.starts_with("/usr/share/icons")
+                    || entry.path.starts_with("/usr/share/pixmaps"))
+                    && entry
+                        .path
+                        .file_stem()
+                        .is_some_and(|stem| stem == name.as_str())
+            })
+            .filter_map(|entry| {
+                let ext = entry.path.extension()?.to_str()?;
+                let rank = ICON_EXTENSIONS.iter().position(|e| *e == ext)?;
+                Some((rank, entry))
+            })
+            .min_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then(b.size.cmp(&a.size)))
+            .map(|(_, entry)| entry);
+        match icon {
+            Some(icon) => self.resolve(icon),
+            None => Ok(None),
+        }
+    }
+
+    /// Find the AppRun entry point at the AppImage root. When AppRun is a symlink,
+    /// usually into usr/bin, it is resolved to the executable it points to.
+    ///
+    /// # Returns
+    /// A SquashFS entry to the AppRun file or its target, if found
+    pub fn app_run(&self) -> Result<Option<SquashFSEntry<'_>>> {
+        match self.squashfs.get_entry("/AppRun") {
+            Some(app_run) => self.resolve(app_run),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolves the entry if it is a symlink, otherwise returns it as is.
+    fn resolve<'s>(&'s self, entry: SquashFSEntry<'s>) -> Result<Option<SquashFSEntry<'s>>> {
+        if entry.is_symlink() {
+            self.squashfs.resolve_symlink(&entry)
+        } else {
+            Ok(Some(entry))
+        }
+    }
+}
+
+/// Icon file extensions, in order of preference.
+const ICON_EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+/// Reads the `Icon` key from the `[Desktop Entry]` group of a desktop file.
+fn desktop_icon_name(desktop: &[u8]) -> Option<String> {
+    let mut in_group = false;
+    for line in String::from_utf8_lossy(desktop).lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+        } else if in_group {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "Icon" && !value.trim().is_empty() {
+                    return Some(value.trim().to_owned());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(all(test, feature = "gzip"))]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::testutil::{build_image, header};
+
+    const DESKTOP: &[u8] =
+        b"[Desktop Action New]\nIcon=other\n\n[Desktop Entry]\nName=App\nIcon = app \n";
+
+    /// Builds an AppDir image:
+    ///
+    /// ```text
+    /// /.DirIcon -> app.png              (with `diricon`)
+    /// /AppRun -> usr/bin/app
+    /// /app.desktop                      DESKTOP
+    /// /app.png                          "root"  (with `diricon`)
+    /// /usr/bin/app                      "#!/bin/sh\n", 0755
+    /// /usr/share/icons/hicolor/256x256/apps/app.png  "large png"
+    /// /usr/share/icons/hicolor/32x32/apps/app.png    "png"
+    /// /usr/share/icons/hicolor/scalable/apps/app.svg "a larger svg icon"
+    /// ```
+    fn appdir_image(diricon: bool) -> Vec<u8> {
+        build_image(|fs| {
+            for dir in [
+                "/usr",
+                "/usr/bin",
+                "/usr/share",
+                "/usr/share/icons",
+                "/usr/share/icons/hicolor",
+                "/usr/share/icons/hicolor/256x256",
+                "/usr/share/icons/hicolor/256x256/apps",
+                "/usr/share/icons/hicolor/32x32",
+                "/usr/share/icons/hicolor/32x32/apps",
+                "/usr/share/icons/hicolor/scalable",
+                "/usr/share/icons/hicolor/scalable/apps",
+            ] {
+                fs.push_dir(dir, header(0o755)).unwrap();
+            }
+
+            let mut files = vec![
+                ("/app.desktop", DESKTOP.to_vec(), 0o644),
+                ("/usr/bin/app", b"#!/bin/sh\n".to_vec(), 0o755),
+                (
+                    "/usr/share/icons/hicolor/256x256/apps/app.png",
+                    b"large png".to_vec(),
+                    0o644,
+                ),
+                (
+                    "/usr/share/icons/hicolor/32x32/apps/app.png",
+                    b"png".to_vec(),
+                    0o644,
+                ),
+                (
+                    "/usr/share/icons/hicolor/scalable/apps/app.svg",
+                    b"a larger svg icon".to_vec(),
+                    0o644,
+                ),
+            ];
+            if diricon {
+                files.push(("/app.png", b"root".to_vec(), 0o644));
+                fs.push_symlink("app.png", "/.DirIcon", header(0o777))
+                    .unwrap();
+            }
+            for (path, contents, mode) in files {
+                fs.push_file(Cursor::new(contents), path, header(mode))
+                    .unwrap();
+            }
+            fs.push_symlink("usr/bin/app", "/AppRun", header(0o777))
+                .unwrap();
+        })
+    }
+
+    fn appimage(image: &[u8]) -> AppImage<'_> {
+        AppImage {
+            filter: None,
+            squashfs: SquashFS::from_bytes(image).unwrap(),
+        }
+    }
+
+    #[test]
+    fn finds_the_root_desktop_entry() {
+        let image = appdir_image(true);
+        let appimage = appimage(&image);
+        let desktop = appimage.find_desktop_entry().unwrap().unwrap();
+        assert_eq!(desktop.path, Path::new("/app.desktop"));
+    }
+
+    #[test]
+    fn app_run_resolves_to_its_target() {
+        let image = appdir_image(true);
+        let appimage = appimage(&image);
+        let app_run = appimage.app_run().unwrap().unwrap();
+        assert_eq!(app_run.path, Path::new("/usr/bin/app"));
+        assert!(app_run.is_file());
+    }
+
+    #[test]
+    fn finds_the_dir_icon_first() {
+        let image = appdir_image(true);
+        let appimage = appimage(&image);
+        let icon = appimage.find_icon_entry().unwrap().unwrap();
+        assert_eq!(icon.path, Path::new("/app.png"));
+    }
+
+    #[test]
+    fn falls_back_to_the_largest_png_in_the_icon_theme() {
+        let image = appdir_image(false);
+        let appimage = appimage(&image);
+        let icon = appimage.find_icon_entry().unwrap().unwrap();
+        assert_eq!(
+            icon.path,
+            Path::new("/usr/share/icons/hicolor/256x256/apps/app.png")
+        );
+    }
+
+    #[test]
+    fn reads_the_icon_key_of_the_desktop_entry_group() {
+        assert_eq!(desktop_icon_name(DESKTOP).as_deref(), Some("app"));
+        assert_eq!(desktop_icon_name(b"[Desktop Entry]\nIcon=\n"), None);
+        assert_eq!(desktop_icon_name(b"[Desktop Action New]\nIcon=app\n"), None);
+    }
+
+    #[test]
+    fn resolve_follows_symlinks_only() {
+        let image = appdir_image(true);
+        let appimage = appimage(&image);
+        let file = appimage.squashfs.get_entry("/app.desktop").unwrap();
+        let resolved = appimage.resolve(file).unwrap().unwrap();
+        assert_eq!(resolved.path, Path::new("/app.desktop"));
+
+        let link = appimage.squashfs.get_entry("/.DirIcon").unwrap();
+        let resolved = appimage.resolve(link).unwrap().unwrap();
+        assert_eq!(resolved.path, Path::new("/app.png"));
+    }
 }
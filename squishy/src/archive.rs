@@ -0,0 +1,270 @@
+#[cfg(feature = "zip")]
+use std::io::Seek;
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use backhand::InnerNode;
+#[cfg(feature = "tar")]
+use tar::{Builder, EntryType, Header};
+#[cfg(feature = "zip")]
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+#[cfg(feature = "tar")]
+use crate::decode_device_number;
+use crate::{file_size, Result, SquashFS};
+
+impl SquashFS<'_> {
+    /// Writes the whole SquashFS filesystem to the writer as a tar archive.
+    ///
+    /// Paths are relative to the image root. Directories, regular files, symlinks,
+    /// device nodes and fifos are written with their mode, owner and modification
+    /// time. Sockets can't be stored in a tar archive and are skipped.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to stream the tar archive into.
+    ///
+    /// # Returns
+    /// An empty result, or an error if an entry cannot be read or written.
+    #[cfg(feature = "tar")]
+    pub fn write_tar<W: Write>(&self, writer: W) -> Result<()> {
+        let mut builder = Builder::new(writer);
+        builder.follow_symlinks(false);
+
+        for node in &self.reader.root.nodes {
+            let path = node.fullpath.strip_prefix("/").unwrap_or(&node.fullpath);
+            if path == Path::new("") {
+                continue;
+            }
+
+            let mut header = Header::new_gnu();
+            header.set_mode(u32::from(node.header.permissions));
+            header.set_uid(u64::from(node.header.uid));
+            header.set_gid(u64::from(node.header.gid));
+            header.set_mtime(u64::from(node.header.mtime));
+            header.set_size(0);
+
+            match &node.inner {
+                InnerNode::Dir(_) => {
+                    header.set_entry_type(EntryType::Directory);
+                    builder.append_data(&mut header, path, io::empty())?;
+                }
+                InnerNode::File(file) => {
+                    header.set_entry_type(EntryType::Regular);
+                    header.set_size(file_size(&file.basic, self.reader.block_size));
+                    let reader = self.file_reader(&file.basic)?;
+                    builder.append_data(&mut header, path, reader)?;
+                }
+                InnerNode::Symlink(symlink) => {
+                    header.set_entry_type(EntryType::Symlink);
+                    builder.append_link(&mut header, path, &symlink.link)?;
+                }
+                InnerNode::CharacterDevice(device) => {
+                    header.set_entry_type(EntryType::Char);
+                    set_device(&mut header, device.device_number)?;
+                    builder.append_data(&mut header, path, io::empty())?;
+                }
+                InnerNode::BlockDevice(device) => {
+                    header.set_entry_type(EntryType::Block);
+                    set_device(&mut header, device.device_number)?;
+                    builder.append_data(&mut header, path, io::empty())?;
+                }
+                InnerNode::NamedPipe => {
+                    header.set_entry_type(EntryType::Fifo);
+                    builder.append_data(&mut header, path, io::empty())?;
+                }
+                InnerNode::Socket => {}
+            }
+        }
+
+        builder.into_inner()?.flush()?;
+        Ok(())
+    }
+
+    /// Writes the whole SquashFS filesystem to the writer as a zip archive.
+    ///
+    /// Paths are relative to the image root and stored as UTF-8, with invalid
+    /// sequences replaced. Files are deflated, and directories and files keep their
+    /// modification time and permission bits, stored in the Unix external attributes.
+    /// Symlinks are stored the way Info-ZIP does: an entry whose Unix mode marks it as
+    /// a symlink and whose contents are the link target, which tools unaware of it
+    /// extract as a small file holding the target. Zip has no notion of owners,
+    /// device nodes, fifos or sockets, so owners are dropped and those are skipped.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the zip archive into.
+    ///
+    /// # Returns
+    /// An empty result, or an error if an entry cannot be read or written.
+    #[cfg(feature = "zip")]
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let mut zip = ZipWriter::new(writer);
+
+        for node in &self.reader.root.nodes {
+            let path = node.fullpath.strip_prefix("/").unwrap_or(&node.fullpath);
+            if path == Path::new("") {
+                continue;
+            }
+            let name = path.to_string_lossy();
+            let mut options = SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .unix_permissions(u32::from(node.header.permissions));
+            if let Some(mtime) = zip_time(node.header.mtime) {
+                options = options.last_modified_time(mtime);
+            }
+
+            match &node.inner {
+                InnerNode::Dir(_) => zip.add_directory(name, options)?,
+                InnerNode::File(file) => {
+                    let size = file_size(&file.basic, self.reader.block_size);
+                    zip.start_file(name, options.large_file(size >= u64::from(u32::MAX)))?;
+                    io::copy(&mut self.file_reader(&file.basic)?, &mut zip)?;
+                }
+                InnerNode::Symlink(symlink) => {
+                    zip.add_symlink(name, symlink.link.to_string_lossy(), options)?
+                }
+                _ => {}
+            }
+        }
+
+        zip.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// Converts a Unix timestamp to a zip (MS-DOS) timestamp, in UTC. Returns None for
+/// times zip can't represent, before 1980 or after 2107.
+#[cfg(feature = "zip")]
+fn zip_time(mtime: u32) -> Option<DateTime> {
+    let days = i64::from(mtime / 86400);
+    let seconds = mtime % 86400;
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        month as u8,
+        day as u8,
+        (seconds / 3600) as u8,
+        (seconds / 60 % 60) as u8,
+        (seconds % 60) as u8,
+    )
+    .ok()
+}
+
+/// Stores a SquashFS device number in the tar header's major and minor fields.
+#[cfg(feature = "tar")]
+fn set_device(header: &mut Header, device_number: u32) -> io::Result<()> {
+    let (major, minor) = decode_device_number(device_number);
+    header.set_device_major(major)?;
+    header.set_device_minor(minor)
+}
+
+#[cfg(all(test, feature = "gzip", any(feature = "tar", feature = "zip")))]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    #[cfg(feature = "tar")]
+    use tar::{Archive, EntryType};
+    #[cfg(feature = "zip")]
+    use zip::ZipArchive;
+
+    use crate::{
+        testutil::{big_contents, sample_image, MTIME},
+        SquashFS,
+    };
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn write_tar_round_trip() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let mut archive = Vec::new();
+        squashfs.write_tar(&mut archive).unwrap();
+
+        let mut paths = Vec::new();
+        for entry in Archive::new(Cursor::new(archive)).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().into_owned();
+            let header = entry.header();
+            let (kind, mode, mtime) = (
+                header.entry_type(),
+                header.mode().unwrap(),
+                header.mtime().unwrap(),
+            );
+            assert_eq!(mtime, u64::from(MTIME), "{path:?}");
+
+            match path.to_str().unwrap() {
+                "usr/bin/a" => {
+                    let mut contents = Vec::new();
+                    entry.read_to_end(&mut contents).unwrap();
+                    assert_eq!((kind, mode), (EntryType::Regular, 0o755));
+                    assert_eq!(contents, b"hello\n");
+                }
+                "usr/bin/big" => {
+                    let mut contents = Vec::new();
+                    entry.read_to_end(&mut contents).unwrap();
+                    assert_eq!((kind, mode), (EntryType::Regular, 0o644));
+                    assert_eq!(contents, big_contents());
+                }
+                "usr/bin/rel" => {
+                    assert_eq!(kind, EntryType::Symlink);
+                    let target = entry.link_name().unwrap().unwrap();
+                    assert_eq!(target.to_str(), Some("big"));
+                }
+                "usr/share" | "usr/share/" => {
+                    assert_eq!((kind, mode), (EntryType::Directory, 0o755));
+                }
+                _ => {}
+            }
+            paths.push(path);
+        }
+
+        let expected: Vec<_> = squashfs
+            .entries()
+            .filter_map(|entry| entry.path.strip_prefix("/").ok().map(|p| p.to_path_buf()))
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect();
+        assert_eq!(paths, expected);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn write_zip_round_trip() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let mut archive = Cursor::new(Vec::new());
+        squashfs.write_zip(&mut archive).unwrap();
+        let mut zip = ZipArchive::new(archive).unwrap();
+
+        let mut contents = Vec::new();
+        let mut file = zip.by_name("usr/bin/big").unwrap();
+        file.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, big_contents());
+        drop(file);
+
+        let file = zip.by_name("usr/bin/a").unwrap();
+        assert!(file.is_file());
+        assert_eq!(file.unix_mode().unwrap() & 0o7777, 0o755);
+        assert_eq!(file.last_modified(), super::zip_time(MTIME));
+        drop(file);
+
+        assert!(zip.by_name("usr/share/").unwrap().is_dir());
+
+        let mut target = String::new();
+        let mut link = zip.by_name("usr/bin/rel").unwrap();
+        assert!(link.is_symlink());
+        link.read_to_string(&mut target).unwrap();
+        assert_eq!(target, "big");
+    }
+}
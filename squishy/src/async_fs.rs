@@ -0,0 +1,110 @@
+use std::{
+    io::{BufReader, Cursor},
+    path::Path,
+    sync::Arc,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    task,
+};
+
+use crate::{error::SquishyError, Result, SquashFS};
+
+/// A SquashFS filesystem usable from async code.
+///
+/// Decompression is still CPU-bound blocking work, it is only moved off the async
+/// executor onto tokio's blocking thread pool with `spawn_blocking`. Must be used
+/// from within a tokio runtime.
+#[derive(Clone)]
+pub struct AsyncSquashFS {
+    inner: Arc<SquashFS<'static>>,
+}
+
+impl AsyncSquashFS {
+    /// Opens the SquashFS filesystem at the given path. Tries to find offset automatically.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SquashFS file.
+    ///
+    /// # Returns
+    /// An AsyncSquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Self::spawn(move || SquashFS::from_path(path)).await
+    }
+
+    /// Reads the whole async reader into memory and opens the SquashFS filesystem in it.
+    /// Tries to find offset automatically.
+    ///
+    /// # Arguments
+    /// * `reader` - The async reader providing the SquashFS data.
+    ///
+    /// # Returns
+    /// An AsyncSquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(mut reader: R) -> Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        Self::spawn(move || SquashFS::new(BufReader::new(Cursor::new(data)), None)).await
+    }
+
+    /// Returns the underlying SquashFS, whose methods block.
+    pub fn inner(&self) -> &SquashFS<'static> {
+        &self.inner
+    }
+
+    /// Reads the contents of the specified file from the SquashFS filesystem.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the file within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The contents of the file, or an error if the file cannot be read.
+    pub async fn read_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref().to_path_buf();
+        self.run_blocking(move |squashfs| squashfs.read_file(path))
+            .await?
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory.
+    /// See [`SquashFS::extract_all`].
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    pub async fn extract_all<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        let dest = dest.as_ref().to_path_buf();
+        self.run_blocking(move |squashfs| squashfs.extract_all(dest))
+            .await?
+    }
+
+    /// Runs a function against the underlying SquashFS on the blocking thread pool.
+    ///
+    /// # Arguments
+    /// * `f` - The function to run, which may call any of the blocking SquashFS methods.
+    ///
+    /// # Returns
+    /// The function's return value, or an error if the blocking task panicked.
+    pub async fn run_blocking<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&SquashFS<'static>) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        task::spawn_blocking(move || f(&inner))
+            .await
+            .map_err(|e| SquishyError::Io(std::io::Error::other(e)))
+    }
+
+    /// Opens a SquashFS on the blocking thread pool.
+    async fn spawn<F>(open: F) -> Result<Self>
+    where
+        F: FnOnce() -> Result<SquashFS<'static>> + Send + 'static,
+    {
+        let squashfs = task::spawn_blocking(open)
+            .await
+            .map_err(|e| SquishyError::Io(std::io::Error::other(e)))??;
+        Ok(Self {
+            inner: Arc::new(squashfs),
+        })
+    }
+}
@@ -1,21 +1,153 @@
 use std::path::PathBuf;
 
+use backhand::BackhandError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum SquishyError {
     #[error("Failed to find SquashFS magic bytes in the file")]
     NoSquashFsFound,
 
-    #[error("IO error: {0}")]
+    #[error(transparent)]
     Io(#[from] std::io::Error),
 
     #[error("SquashFS error: {0}")]
     InvalidSquashFS(String),
 
+    #[error(transparent)]
+    Backhand(#[from] BackhandError),
+
     #[error("Symlink error: {0}")]
     SymlinkError(String),
 
     #[error("File not found: {0}")]
     FileNotFound(PathBuf),
+
+    #[error("No entry with inode number {0}")]
+    InodeNotFound(u32),
+
+    #[error("Not a directory: {0}")]
+    NotADirectory(PathBuf),
+
+    #[error("Not a file: {0}")]
+    NotAFile(PathBuf),
+
+    #[error("Unknown entry kind: {0}")]
+    UnknownEntryKind(String),
+
+    #[error("Invalid UTF-8 in file: {0}")]
+    InvalidUtf8(PathBuf),
+
+    #[error("Unsupported compression: compressor id {0}")]
+    UnsupportedCompression(u16),
+
+    #[error("File too large to read: {0} bytes, at most 4 GiB are supported")]
+    FileTooLarge(u64),
+
+    #[cfg(feature = "glob")]
+    #[error(transparent)]
+    InvalidGlob(#[from] globset::Error),
+
+    #[cfg(feature = "zip")]
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Operation was cancelled")]
+    Cancelled,
+
+    #[error("Refusing to extract outside of destination: {0}")]
+    UnsafePath(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io};
+
+    use super::*;
+
+    #[test]
+    fn formats_each_variant() {
+        let cases = [
+            (
+                SquishyError::NoSquashFsFound,
+                "Failed to find SquashFS magic bytes in the file",
+            ),
+            (
+                io::Error::new(io::ErrorKind::NotFound, "gone").into(),
+                "gone",
+            ),
+            (
+                SquishyError::InvalidSquashFS("bad superblock".into()),
+                "SquashFS error: bad superblock",
+            ),
+            (BackhandError::FileNotFound.into(), "file not found"),
+            (
+                SquishyError::SymlinkError("cycle".into()),
+                "Symlink error: cycle",
+            ),
+            (
+                SquishyError::FileNotFound("/usr/bin/foo".into()),
+                "File not found: /usr/bin/foo",
+            ),
+            (
+                SquishyError::InodeNotFound(7),
+                "No entry with inode number 7",
+            ),
+            (
+                SquishyError::NotADirectory("/a".into()),
+                "Not a directory: /a",
+            ),
+            (SquishyError::NotAFile("/usr".into()), "Not a file: /usr"),
+            (
+                SquishyError::UnknownEntryKind("pipe".into()),
+                "Unknown entry kind: pipe",
+            ),
+            (
+                SquishyError::InvalidUtf8("/bin".into()),
+                "Invalid UTF-8 in file: /bin",
+            ),
+            (
+                SquishyError::UnsupportedCompression(9),
+                "Unsupported compression: compressor id 9",
+            ),
+            (
+                SquishyError::FileTooLarge(1 << 33),
+                "File too large to read: 8589934592 bytes, at most 4 GiB are supported",
+            ),
+            (SquishyError::Cancelled, "Operation was cancelled"),
+            (
+                SquishyError::UnsafePath("../etc".into()),
+                "Refusing to extract outside of destination: ../etc",
+            ),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn wrapped_errors_keep_their_source() {
+        let error = SquishyError::from(BackhandError::StdIo(io::Error::other("gone")));
+        assert_eq!(error.to_string(), "std io error: gone");
+        assert_eq!(error.source().unwrap().to_string(), "gone");
+    }
+
+    #[cfg(feature = "glob")]
+    #[test]
+    fn formats_glob_errors_once() {
+        let glob = globset::Glob::new("a[").unwrap_err();
+        assert_eq!(
+            SquishyError::from(glob.clone()).to_string(),
+            glob.to_string()
+        );
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn formats_zip_errors_once() {
+        let zip = zip::result::ZipError::FileNotFound;
+        let message = zip.to_string();
+        assert_eq!(SquishyError::from(zip).to_string(), message);
+    }
 }
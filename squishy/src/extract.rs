@@ -0,0 +1,868 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, Permissions},
+    io::{BufWriter, ErrorKind, Read, Write},
+    ops::Range,
+    os::unix::{self, fs::PermissionsExt},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use backhand::{InnerNode, Node, SquashfsFileReader};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    decode_device_number, error::SquishyError, file_size, ExtractOptions, ExtractReport, OnError,
+    Result, SquashFS,
+};
+
+/// Size of the chunks file data is copied in during extraction, unless set with
+/// [`crate::SquashFSBuilder::read_buffer_size`].
+const EXTRACT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Callback invoked during extraction with the current path and the number of bytes just written.
+type Hook<'h> = dyn FnMut(&Path, u64) -> Result<()> + 'h;
+
+/// Callback invoked during extraction with the path of an entry that failed and the error.
+type ErrorHandler<'h> = dyn FnMut(&Path, &SquishyError) -> OnError + 'h;
+
+impl SquashFS<'_> {
+    /// Extracts the whole SquashFS filesystem into the destination directory.
+    ///
+    /// Regular files are written with their permissions and mtime, and symlinks are recreated
+    /// as symlinks with their literal target, even if it is relative or dangling.
+    /// Directories get their recorded permissions and mtime once everything has been
+    /// written, deepest first, so read-only directories can still be filled.
+    /// Device nodes, fifos and sockets are skipped. Paths sharing an inode are written
+    /// as hardlinks of the first one, see [`ExtractOptions::hardlinks`].
+    /// Extended attributes are not applied, as backhand doesn't read the xattr table.
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    ///
+    /// # Returns
+    /// An empty result, or an error if an entry cannot be written or would be
+    /// written outside of `dest`.
+    pub fn extract_all<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        self.extract_all_with_options(dest, ExtractOptions::default())
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory,
+    /// as configured by the options.
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    /// * `options` - How to write the entries, see [`ExtractOptions`].
+    ///
+    /// # Returns
+    /// An empty result, or an error if an entry cannot be written or would be
+    /// written outside of `dest`.
+    pub fn extract_all_with_options<P: AsRef<Path>>(
+        &self,
+        dest: P,
+        options: ExtractOptions,
+    ) -> Result<()> {
+        self.extract_all_with_report(dest, options).map(|_| ())
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory, as
+    /// configured by the options, and reports which entries couldn't be extracted.
+    ///
+    /// With [`OnError::Skip`], an entry that fails, e.g. a corrupt file, is recorded
+    /// and the extraction goes on with the next one, so everything extractable from a
+    /// damaged image is salvaged. Whatever was written of a failed file is left in place.
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    /// * `options` - How to write the entries, see [`ExtractOptions`].
+    ///
+    /// # Returns
+    /// A report of the entries extracted and the failures skipped, or the first error
+    /// with [`OnError::Abort`].
+    pub fn extract_all_with_report<P: AsRef<Path>>(
+        &self,
+        dest: P,
+        options: ExtractOptions,
+    ) -> Result<ExtractReport> {
+        self.extract_all_with_error_handler(dest, options, |_, _| options.on_error)
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory, letting
+    /// the callback decide what to do each time an entry cannot be extracted.
+    ///
+    /// This is the same as [`SquashFS::extract_all_with_report`], except that the
+    /// policy set with [`ExtractOptions::on_error`] is not used.
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    /// * `options` - How to write the entries, see [`ExtractOptions`].
+    /// * `on_error` - A function called with the path of the entry and the error,
+    ///   returning whether to abort or skip it.
+    ///
+    /// # Returns
+    /// A report of the entries extracted and the failures skipped, or the error the
+    /// callback aborted on.
+    pub fn extract_all_with_error_handler<P, F>(
+        &self,
+        dest: P,
+        options: ExtractOptions,
+        mut on_error: F,
+    ) -> Result<ExtractReport>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Path, &SquishyError) -> OnError,
+    {
+        self.extract_nodes(
+            0..self.reader.root.nodes.len(),
+            Path::new("/"),
+            dest.as_ref(),
+            options,
+            &mut |_, _| Ok(()),
+            &mut on_error,
+        )
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory,
+    /// reporting progress along the way.
+    ///
+    /// The callback receives the path of the entry being extracted, the number of
+    /// bytes written so far and the total number of bytes to write (see
+    /// [`SquashFS::total_size`]). It is called once per entry and after every chunk
+    /// of up to 64 KiB written for a file, or of the size set with
+    /// [`crate::SquashFSBuilder::read_buffer_size`].
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    /// * `progress` - A function called with the current path, bytes done and total bytes.
+    ///
+    /// # Returns
+    /// An empty result, or an error if an entry cannot be written or would be
+    /// written outside of `dest`.
+    pub fn extract_all_with_progress<P, F>(&self, dest: P, mut progress: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Path, u64, u64),
+    {
+        let total = self.total_size();
+        let mut done = 0;
+        self.extract_nodes(
+            0..self.reader.root.nodes.len(),
+            Path::new("/"),
+            dest.as_ref(),
+            ExtractOptions::default(),
+            &mut |path, written| {
+                done += written;
+                progress(path, done, total);
+                Ok(())
+            },
+            &mut |_, _| OnError::Abort,
+        )
+        .map(|_| ())
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory,
+    /// stopping early once `cancel` is set.
+    ///
+    /// The flag is checked before each entry and between chunks of file data.
+    /// On cancellation, whatever was already written, including a partially
+    /// written file, is left in place.
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    /// * `cancel` - A flag that can be set from another thread to stop the extraction.
+    ///
+    /// # Returns
+    /// An empty result, [`SquishyError::Cancelled`] if the extraction was cancelled,
+    /// or an error if an entry cannot be written or would be written outside of `dest`.
+    pub fn extract_all_cancellable<P: AsRef<Path>>(
+        &self,
+        dest: P,
+        cancel: &AtomicBool,
+    ) -> Result<()> {
+        self.extract_nodes(
+            0..self.reader.root.nodes.len(),
+            Path::new("/"),
+            dest.as_ref(),
+            ExtractOptions::default(),
+            &mut |_, _| {
+                if cancel.load(Ordering::Relaxed) {
+                    Err(SquishyError::Cancelled)
+                } else {
+                    Ok(())
+                }
+            },
+            &mut |_, _| OnError::Abort,
+        )
+        .map(|_| ())
+    }
+
+    /// Extracts a single directory of the SquashFS filesystem, and everything
+    /// under it, into the destination directory.
+    ///
+    /// Entries are written the same way as with [`SquashFS::extract_all`], with
+    /// `source_dir` itself mapped to `dest`.
+    ///
+    /// # Arguments
+    /// * `source_dir` - The full path of the directory within the SquashFS filesystem.
+    /// * `dest` - The directory to extract the subtree into.
+    ///
+    /// # Returns
+    /// An empty result, or an error if `source_dir` is not a directory, or if an
+    /// entry cannot be written or would be written outside of `dest`.
+    pub fn extract_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        source_dir: P,
+        dest: Q,
+    ) -> Result<()> {
+        let range = self.subtree(source_dir)?;
+        self.extract_nodes(
+            range.clone(),
+            &self.reader.root.nodes[range.start].fullpath,
+            dest.as_ref(),
+            ExtractOptions::default(),
+            &mut |_, _| Ok(()),
+            &mut |_, _| OnError::Abort,
+        )
+        .map(|_| ())
+    }
+
+    /// Extracts a single file into `out_root` at the same path it has in the SquashFS
+    /// filesystem, creating the directories leading to it. `/usr/bin/foo` is written to
+    /// `<out_root>/usr/bin/foo`, with its permissions.
+    ///
+    /// # Arguments
+    /// * `source` - The full path of the file within the SquashFS filesystem.
+    /// * `out_root` - The directory to extract the file under.
+    ///
+    /// # Returns
+    /// The path the file was written to, or an error if `source` is not a file, or if
+    /// it cannot be written or would be written outside of `out_root`.
+    pub fn extract_file_to_root<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        source: P,
+        out_root: Q,
+    ) -> Result<PathBuf> {
+        let source = source.as_ref();
+        let node = self
+            .node(source)
+            .ok_or_else(|| SquishyError::FileNotFound(source.to_path_buf()))?;
+        if !matches!(node.inner, InnerNode::File(_)) {
+            return Err(SquishyError::NotAFile(source.to_path_buf()));
+        }
+        let Some(output_path) = output_path(out_root.as_ref(), &node.fullpath)? else {
+            return Err(SquishyError::NotAFile(source.to_path_buf()));
+        };
+
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.extract_node(node, &output_path, &mut |_, _| Ok(()))?;
+        Ok(output_path)
+    }
+
+    /// Extracts the nodes in the index range into `dest`, placing each one at its path
+    /// relative to `root`.
+    ///
+    /// The hook is called with the path of each node before it is extracted, and
+    /// with the number of bytes written after each chunk of file data, or with the
+    /// size of a file linked rather than written. Returning an error from it stops
+    /// the extraction, unless the error handler says to skip the entry.
+    fn extract_nodes(
+        &self,
+        range: Range<usize>,
+        root: &Path,
+        dest: &Path,
+        options: ExtractOptions,
+        hook: &mut Hook,
+        on_error: &mut ErrorHandler,
+    ) -> Result<ExtractReport> {
+        fs::create_dir_all(dest)?;
+        let mut report = ExtractReport::default();
+        let mut dirs = Vec::new();
+        let mut links: HashMap<u32, PathBuf> = HashMap::new();
+
+        let nodes = &self.reader.root.nodes[range.clone()];
+        for (node, &inode) in nodes.iter().zip(&self.inodes[range]) {
+            let path = node.fullpath.strip_prefix(root).unwrap_or(&node.fullpath);
+            let result = output_path(dest, path).and_then(|output_path| {
+                let Some(output_path) = output_path else {
+                    return Ok(false);
+                };
+                hook(&node.fullpath, 0)?;
+
+                match &node.inner {
+                    InnerNode::Dir(_) => {
+                        fs::create_dir_all(&output_path)?;
+                        dirs.push((node, output_path));
+                    }
+                    InnerNode::File(file) if options.hardlinks => match links.get(&inode) {
+                        Some(original) => {
+                            hard_link(original, &output_path)?;
+                            hook(
+                                &node.fullpath,
+                                file_size(&file.basic, self.reader.block_size),
+                            )?;
+                        }
+                        None => {
+                            self.extract_node(node, &output_path, hook)?;
+                            links.insert(inode, output_path);
+                        }
+                    },
+                    #[cfg(unix)]
+                    InnerNode::BlockDevice(_)
+                    | InnerNode::CharacterDevice(_)
+                    | InnerNode::NamedPipe
+                        if options.devices =>
+                    {
+                        make_node(node, &output_path)?;
+                        return Ok(true);
+                    }
+                    _ => self.extract_node(node, &output_path, hook)?,
+                }
+                Ok(matches!(
+                    node.inner,
+                    InnerNode::Dir(_) | InnerNode::File(_) | InnerNode::Symlink(_)
+                ))
+            });
+
+            match result {
+                Ok(extracted) => report.extracted += usize::from(extracted),
+                Err(e) => handle_error(&mut report, on_error, node, e)?,
+            }
+        }
+
+        // Directories are only done once their mtime and permissions are applied.
+        for (node, output_path) in dirs.iter().rev() {
+            if let Err(e) = finish_dir(node, output_path) {
+                report.extracted -= 1;
+                handle_error(&mut report, on_error, node, e)?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Extracts the whole SquashFS filesystem into the destination directory,
+    /// decompressing and writing files in parallel.
+    ///
+    /// The result is the same as with [`SquashFS::extract_all`]. Directories are
+    /// created first, then files and symlinks are written across the rayon thread
+    /// pool, then the hardlinks to files sharing an inode are created, and directory
+    /// permissions and mtimes are applied last.
+    ///
+    /// # Arguments
+    /// * `dest` - The directory to extract the filesystem into.
+    ///
+    /// # Returns
+    /// An empty result, or an error if an entry cannot be written or would be
+    /// written outside of `dest`.
+    #[cfg(feature = "rayon")]
+    pub fn extract_all_parallel<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        let dest = dest.as_ref();
+        fs::create_dir_all(dest)?;
+
+        let mut dirs = Vec::new();
+        let mut others = Vec::new();
+        let mut originals: HashMap<u32, PathBuf> = HashMap::new();
+        let mut links = Vec::new();
+        for (node, &inode) in self.reader.root.nodes.iter().zip(&self.inodes) {
+            let Some(output_path) = output_path(dest, &node.fullpath)? else {
+                continue;
+            };
+            match &node.inner {
+                InnerNode::Dir(_) => {
+                    fs::create_dir_all(&output_path)?;
+                    dirs.push((node, output_path));
+                }
+                InnerNode::File(_) => match originals.get(&inode) {
+                    Some(original) => links.push((original.clone(), output_path)),
+                    None => {
+                        originals.insert(inode, output_path.clone());
+                        others.push((node, output_path));
+                    }
+                },
+                _ => others.push((node, output_path)),
+            }
+        }
+
+        others.par_iter().try_for_each(|(node, output_path)| {
+            self.extract_node(node, output_path, &mut |_, _| Ok(()))
+        })?;
+        for (original, link) in &links {
+            hard_link(original, link)?;
+        }
+
+        finish_dirs(&dirs)
+    }
+
+    /// Writes a single non-directory node to `output_path`, calling the hook
+    /// after each chunk of file data. Files get their recorded permissions and mtime. Device nodes, fifos and sockets are skipped.
+    fn extract_node(
+        &self,
+        node: &Node<SquashfsFileReader>,
+        output_path: &Path,
+        hook: &mut Hook,
+    ) -> Result<()> {
+        match &node.inner {
+            InnerNode::File(file) => {
+                remove_symlink(output_path)?;
+                let output_file = File::create(output_path)?;
+                let mode = u32::from(node.header.permissions);
+                fs::set_permissions(output_path, Permissions::from_mode(mode))?;
+
+                let mut writer = BufWriter::new(output_file);
+                let mut reader = self.file_reader(&file.basic)?;
+                let mut buf = vec![0; self.copy_buffer_size.unwrap_or(EXTRACT_CHUNK_SIZE)];
+                loop {
+                    let n = match reader.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(n) => n,
+                        Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e.into()),
+                    };
+                    writer.write_all(&buf[..n])?;
+                    hook(&node.fullpath, n as u64)?;
+                }
+                let output_file = writer.into_inner().map_err(|e| e.into_error())?;
+                output_file.set_modified(mtime(node))?;
+            }
+            InnerNode::Symlink(symlink) => {
+                remove_symlink(output_path)?;
+                unix::fs::symlink(&symlink.link, output_path)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Maps a path inside the SquashFS filesystem to its location under `dest`.
+///
+/// Returns `None` for the root itself, and an error if the path contains
+/// components that would escape `dest` or if any of its parents under `dest`
+/// is a symlink.
+fn output_path(dest: &Path, path: &Path) -> Result<Option<PathBuf>> {
+    let mut output = dest.to_path_buf();
+    let mut components = path.components().peekable();
+
+    while let Some(component) = components.next() {
+        match component {
+            Component::RootDir | Component::CurDir => {}
+            Component::Normal(name) => {
+                output.push(name);
+                if components.peek().is_some() && output.is_symlink() {
+                    return Err(SquishyError::UnsafePath(path.to_path_buf()));
+                }
+            }
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(SquishyError::UnsafePath(path.to_path_buf()));
+            }
+        }
+    }
+
+    Ok((output != dest).then_some(output))
+}
+
+/// Applies the recorded mtime and permissions of the extracted directories, given
+/// in path order. Children are handled before their parent, so a read-only parent
+/// doesn't stop them from being updated.
+#[cfg(feature = "rayon")]
+fn finish_dirs(dirs: &[(&Node<SquashfsFileReader>, PathBuf)]) -> Result<()> {
+    for (node, output_path) in dirs.iter().rev() {
+        finish_dir(node, output_path)?;
+    }
+    Ok(())
+}
+
+/// Applies the recorded mtime and permissions of an extracted directory.
+fn finish_dir(node: &Node<SquashfsFileReader>, output_path: &Path) -> Result<()> {
+    File::open(output_path)?.set_modified(mtime(node))?;
+    let mode = u32::from(node.header.permissions);
+    fs::set_permissions(output_path, Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+/// Asks the error handler what to do about the node that failed, recording the
+/// error in the report if it is skipped and returning it otherwise.
+fn handle_error(
+    report: &mut ExtractReport,
+    on_error: &mut ErrorHandler,
+    node: &Node<SquashfsFileReader>,
+    e: SquishyError,
+) -> Result<()> {
+    match on_error(&node.fullpath, &e) {
+        OnError::Abort => Err(e),
+        OnError::Skip => {
+            report.failures.push((node.fullpath.clone(), e));
+            Ok(())
+        }
+    }
+}
+
+/// Returns the modification time recorded for the node.
+fn mtime(node: &Node<SquashfsFileReader>) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(u64::from(node.header.mtime))
+}
+
+/// Creates `link` as a hardlink of `original`, replacing whatever file or symlink
+/// is already at `link`.
+fn hard_link(original: &Path, link: &Path) -> Result<()> {
+    if link
+        .symlink_metadata()
+        .is_ok_and(|metadata| !metadata.is_dir())
+    {
+        fs::remove_file(link)?;
+    }
+    fs::hard_link(original, link)?;
+    Ok(())
+}
+
+/// Creates the device node or fifo at `path` with `mknod`, replacing whatever file or
+/// symlink is already there, and applies the node's permissions.
+#[cfg(unix)]
+fn make_node(node: &Node<SquashfsFileReader>, path: &Path) -> Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let (kind, device_number) = match &node.inner {
+        InnerNode::BlockDevice(device) => (libc::S_IFBLK, device.device_number),
+        InnerNode::CharacterDevice(device) => (libc::S_IFCHR, device.device_number),
+        InnerNode::NamedPipe => (libc::S_IFIFO, 0),
+        _ => return Ok(()),
+    };
+    let (major, minor) = decode_device_number(device_number);
+
+    if path
+        .symlink_metadata()
+        .is_ok_and(|metadata| !metadata.is_dir())
+    {
+        fs::remove_file(path)?;
+    }
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| SquishyError::UnsafePath(path.to_path_buf()))?;
+    let mode = kind | libc::mode_t::from(node.header.permissions);
+    // SAFETY: `c_path` is a valid NUL-terminated string that outlives the call.
+    if unsafe { libc::mknod(c_path.as_ptr(), mode, libc::makedev(major, minor)) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    fs::set_permissions(
+        path,
+        Permissions::from_mode(u32::from(node.header.permissions)),
+    )?;
+    Ok(())
+}
+
+/// Removes an existing symlink at the path, so it is replaced rather than
+/// written through.
+fn remove_symlink(path: &Path) -> Result<()> {
+    if path.is_symlink() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "gzip"))]
+mod tests {
+    use std::io::{BufReader, Cursor};
+
+    use super::*;
+    use crate::testutil::{big_contents, build_image, hardlink_image, header, sample_image, MTIME};
+
+    #[test]
+    fn extract_all_round_trip() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        squashfs.extract_all(dest.path()).unwrap();
+
+        let root = dest.path();
+        assert_eq!(fs::read(root.join("usr/bin/a")).unwrap(), b"hello\n");
+        assert_eq!(fs::read(root.join("usr/bin/big")).unwrap(), big_contents());
+        assert_eq!(
+            fs::read(root.join("usr/share/icons/hicolor/app.png")).unwrap(),
+            b"png"
+        );
+        let mode = fs::metadata(root.join("usr/bin/a"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o755);
+        assert_eq!(
+            fs::read_link(root.join("usr/bin/rel")).unwrap(),
+            Path::new("big")
+        );
+        assert_eq!(
+            fs::read_link(root.join("dangling")).unwrap(),
+            Path::new("missing")
+        );
+
+        let extracted = walk(root);
+        let expected: Vec<_> = squashfs
+            .entries()
+            .filter_map(|entry| entry.path.strip_prefix("/").ok().map(Path::to_path_buf))
+            .filter(|path| path != Path::new(""))
+            .collect();
+        assert_eq!(extracted, expected);
+    }
+
+    #[test]
+    fn paths_sharing_an_inode_are_extracted_as_hardlinks() {
+        use std::os::unix::fs::MetadataExt;
+
+        let image = hardlink_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let inode = |root: &Path, path: &str| fs::metadata(root.join(path)).unwrap().ino();
+
+        let dest = tempfile::tempdir().unwrap();
+        let root = dest.path();
+        squashfs.extract_all(root).unwrap();
+        assert_eq!(inode(root, "hardlink_a"), inode(root, "hardlink_b"));
+        assert_ne!(inode(root, "hardlink_a"), inode(root, "other"));
+        assert_eq!(fs::read(root.join("hardlink_b")).unwrap(), b"linked");
+
+        let dest = tempfile::tempdir().unwrap();
+        let root = dest.path();
+        squashfs
+            .extract_all_with_options(root, ExtractOptions::new().hardlinks(false))
+            .unwrap();
+        assert_ne!(inode(root, "hardlink_a"), inode(root, "hardlink_b"));
+        assert_eq!(fs::read(root.join("hardlink_b")).unwrap(), b"linked");
+
+        #[cfg(feature = "rayon")]
+        {
+            let dest = tempfile::tempdir().unwrap();
+            let root = dest.path();
+            squashfs.extract_all_parallel(root).unwrap();
+            assert_eq!(inode(root, "hardlink_a"), inode(root, "hardlink_b"));
+            assert_ne!(inode(root, "hardlink_a"), inode(root, "other"));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_extraction_matches_serial_extraction() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let serial = tempfile::tempdir().unwrap();
+        squashfs.extract_all(serial.path()).unwrap();
+        let parallel = tempfile::tempdir().unwrap();
+        squashfs.extract_all_parallel(parallel.path()).unwrap();
+
+        let paths = walk(serial.path());
+        assert_eq!(walk(parallel.path()), paths);
+        for path in paths {
+            let (serial, parallel) = (serial.path().join(&path), parallel.path().join(&path));
+            let (expected, actual) = (
+                serial.symlink_metadata().unwrap(),
+                parallel.symlink_metadata().unwrap(),
+            );
+            assert_eq!(actual.file_type(), expected.file_type(), "{path:?}");
+            assert_eq!(actual.permissions(), expected.permissions(), "{path:?}");
+            if expected.is_symlink() {
+                assert_eq!(
+                    fs::read_link(parallel).unwrap(),
+                    fs::read_link(serial).unwrap()
+                );
+            } else if expected.is_file() {
+                assert_eq!(
+                    fs::read(parallel).unwrap(),
+                    fs::read(serial).unwrap(),
+                    "{path:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn extracted_files_keep_their_mtime() {
+        let image = build_image(|fs| {
+            let mut old = header(0o644);
+            old.mtime = 1_000_000_000;
+            fs.push_file(Cursor::new(b"old".to_vec()), "/old", old)
+                .unwrap();
+            fs.push_file(Cursor::new(big_contents()), "/big", header(0o644))
+                .unwrap();
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        squashfs.extract_all(dest.path()).unwrap();
+
+        for (path, expected) in [("old", 1_000_000_000), ("big", MTIME)] {
+            let modified = fs::metadata(dest.path().join(path))
+                .unwrap()
+                .modified()
+                .unwrap();
+            let entry = squashfs.get_entry(Path::new("/").join(path)).unwrap();
+            assert_eq!(entry.mtime, expected, "{path}");
+            assert_eq!(
+                modified,
+                UNIX_EPOCH + Duration::from_secs(u64::from(expected)),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_only_directories_are_finished_after_their_contents() {
+        let image = build_image(|fs| {
+            fs.push_dir("/ro", header(0o555)).unwrap();
+            fs.push_dir("/ro/sub", header(0o555)).unwrap();
+            fs.push_file(Cursor::new(b"top".to_vec()), "/ro/file", header(0o644))
+                .unwrap();
+            fs.push_file(
+                Cursor::new(b"nested".to_vec()),
+                "/ro/sub/file",
+                header(0o644),
+            )
+            .unwrap();
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        squashfs.extract_all(dest.path()).unwrap();
+
+        let root = dest.path();
+        assert_eq!(fs::read(root.join("ro/file")).unwrap(), b"top");
+        assert_eq!(fs::read(root.join("ro/sub/file")).unwrap(), b"nested");
+        for dir in ["ro", "ro/sub"] {
+            let metadata = fs::metadata(root.join(dir)).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o7777, 0o555, "{dir}");
+            let mtime = UNIX_EPOCH + Duration::from_secs(u64::from(MTIME));
+            assert_eq!(metadata.modified().unwrap(), mtime, "{dir}");
+        }
+
+        // Let the temporary directory be removed.
+        for dir in ["ro", "ro/sub"] {
+            fs::set_permissions(root.join(dir), Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn directories_that_cannot_be_finished_go_through_the_error_handler() {
+        let image = build_image(|fs| {
+            fs.push_dir("/empty", header(0o755)).unwrap();
+            fs.push_dir("/linked", header(0o755)).unwrap();
+            fs.push_file(Cursor::new(b"x".to_vec()), "/linked/file", header(0o644))
+                .unwrap();
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        unix::fs::symlink(elsewhere.path(), dest.path().join("linked")).unwrap();
+
+        // Writing through the symlink fails, and the handler then removes /empty
+        // so its mtime and permissions can't be applied.
+        let report = squashfs
+            .extract_all_with_error_handler(dest.path(), ExtractOptions::new(), |path, _| {
+                if path == Path::new("/linked/file") {
+                    fs::remove_dir(dest.path().join("empty")).unwrap();
+                }
+                OnError::Skip
+            })
+            .unwrap();
+        let failed: Vec<_> = report.failures.iter().map(|(path, _)| path).collect();
+        assert_eq!(failed, [Path::new("/linked/file"), Path::new("/empty")]);
+        assert!(matches!(report.failures[1].1, SquishyError::Io(_)));
+        assert_eq!(report.extracted, 1);
+
+        let result = squashfs.extract_all_with_error_handler(
+            dest.path(),
+            ExtractOptions::new(),
+            |path, _| {
+                if path == Path::new("/linked/file") {
+                    fs::remove_dir(dest.path().join("empty")).unwrap();
+                    OnError::Skip
+                } else {
+                    OnError::Abort
+                }
+            },
+        );
+        assert!(matches!(result, Err(SquishyError::Io(_))));
+    }
+
+    #[test]
+    fn extract_all_refuses_to_write_through_symlinks() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        unix::fs::symlink(outside.path(), dest.path().join("usr")).unwrap();
+
+        let result = squashfs.extract_all(dest.path());
+        assert!(matches!(result, Err(SquishyError::UnsafePath(_))));
+        assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn extraction_copies_through_the_configured_buffer() {
+        let image = sample_image();
+        let squashfs = SquashFS::builder()
+            .read_buffer_size(1000)
+            .open_reader(BufReader::new(Cursor::new(&image)))
+            .unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let mut chunks = 0;
+        squashfs
+            .extract_all_with_progress(dest.path(), |path, _, _| {
+                chunks += usize::from(path == Path::new("/usr/bin/big"));
+            })
+            .unwrap();
+        // One call before the file is written, then one per chunk of at most 1000 bytes.
+        assert!(chunks > big_contents().len() / 1000, "{chunks} chunks");
+        assert_eq!(
+            fs::read(dest.path().join("usr/bin/big")).unwrap(),
+            big_contents()
+        );
+    }
+
+    #[test]
+    fn zero_buffer_size_is_clamped() {
+        let image = sample_image();
+        let squashfs = SquashFS::builder()
+            .read_buffer_size(0)
+            .open_reader(BufReader::new(Cursor::new(&image)))
+            .unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        squashfs.extract_all(dest.path()).unwrap();
+        assert_eq!(fs::read(dest.path().join("usr/bin/a")).unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn output_path_rejects_traversal() {
+        let dest = tempfile::tempdir().unwrap();
+        let dest = dest.path();
+
+        assert_eq!(output_path(dest, Path::new("/")).unwrap(), None);
+        assert_eq!(
+            output_path(dest, Path::new("/usr/bin")).unwrap(),
+            Some(dest.join("usr/bin"))
+        );
+        for path in ["/../etc/passwd", "/usr/../../etc/passwd", "../x"] {
+            assert!(
+                matches!(
+                    output_path(dest, Path::new(path)),
+                    Err(SquishyError::UnsafePath(_))
+                ),
+                "{path}"
+            );
+        }
+    }
+
+    /// Returns the paths of everything under `root`, relative to it and sorted.
+    fn walk(root: &Path) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.symlink_metadata().unwrap().is_dir() {
+                    pending.push(path.clone());
+                }
+                paths.push(path.strip_prefix(root).unwrap().to_path_buf());
+            }
+        }
+        paths.sort();
+        paths
+    }
+}
@@ -0,0 +1,206 @@
+//! Inode numbers of the entries. backhand reads them to link directory entries to
+//! their inodes but doesn't keep them on its nodes, so the directory tree is walked
+//! again here, from the inode and directory tables.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    hash::BuildHasher,
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+};
+
+use backhand::compression::{CompressionAction, Compressor, DefaultCompressor};
+
+use crate::{
+    error::SquishyError,
+    scan::{Endian, METADATA_SIZE, METADATA_UNCOMPRESSED},
+    Result,
+};
+
+/// Inode types of the basic and extended directory inodes.
+const BASIC_DIRECTORY: u16 = 1;
+const EXTENDED_DIRECTORY: u16 = 8;
+
+/// A metadata table with its blocks decompressed: the offset of each block in the
+/// table mapped to where its data starts, and the data of all the blocks. This is
+/// how backhand keeps the directory table.
+type Table<S> = (HashMap<u64, u64, S>, Vec<u8>);
+
+/// Decompresses the metadata blocks of a table read raw from the image.
+///
+/// # Arguments
+/// * `raw` - The table as stored in the image.
+/// * `endian` - Byte order of the block headers.
+/// * `compressor` - Compressor of the image.
+///
+/// # Returns
+/// The decompressed table, or an error if a block is truncated or cannot be decompressed.
+pub(crate) fn decompress_table(
+    raw: &[u8],
+    endian: Endian,
+    compressor: Compressor,
+) -> Result<Table<std::hash::RandomState>> {
+    let mut blocks = HashMap::new();
+    let mut bytes = Vec::new();
+    let mut at = 0;
+    while at < raw.len() {
+        let header = read_u16(raw, at, endian).ok_or_else(corrupted)?;
+        let len = usize::from(header & !METADATA_UNCOMPRESSED);
+        let block = raw.get(at + 2..at + 2 + len).ok_or_else(corrupted)?;
+
+        blocks.insert(at as u64, bytes.len() as u64);
+        if header & METADATA_UNCOMPRESSED != 0 {
+            bytes.extend_from_slice(block);
+        } else {
+            let mut data = Vec::with_capacity(METADATA_SIZE);
+            DefaultCompressor.decompress(block, &mut data, compressor)?;
+            bytes.append(&mut data);
+        }
+        at += 2 + len;
+    }
+    Ok((blocks, bytes))
+}
+
+/// Returns the inode number of every path in the image, walking the directory tree
+/// from the root directory.
+///
+/// # Arguments
+/// * `inodes` - The decompressed inode table.
+/// * `dirs` - The decompressed directory table.
+/// * `root_inode` - Location of the root directory inode, as recorded in the superblock.
+/// * `endian` - Byte order of the inodes and directory entries.
+///
+/// # Returns
+/// The inode numbers by full path, or an error if the tables are inconsistent.
+pub(crate) fn inode_numbers<S, T>(
+    inodes: &Table<S>,
+    dirs: &Table<T>,
+    root_inode: u64,
+    endian: Endian,
+) -> Result<HashMap<PathBuf, u32>>
+where
+    S: BuildHasher,
+    T: BuildHasher,
+{
+    let inode_at = |block: u64, offset: usize| {
+        let start = *inodes.0.get(&block)? as usize;
+        inodes.1.get(start.checked_add(offset)?..)
+    };
+
+    let root = inode_at(root_inode >> 16, (root_inode & 0xffff) as usize).ok_or_else(corrupted)?;
+    let mut numbers = HashMap::new();
+    numbers.insert(
+        PathBuf::from("/"),
+        read_u32(root, 12, endian).ok_or_else(corrupted)?,
+    );
+
+    let mut visited = HashSet::new();
+    let mut pending = vec![(PathBuf::from("/"), root)];
+    while let Some((path, inode)) = pending.pop() {
+        let (block, offset, size) = dir_location(inode, endian).ok_or_else(corrupted)?;
+        // The size counts the `.` and `..` entries, which aren't stored.
+        if size < 4 {
+            continue;
+        }
+        // A directory reached twice would be walked forever.
+        if !visited.insert((block, offset)) {
+            return Err(corrupted());
+        }
+
+        let mut listing = dirs
+            .0
+            .get(&u64::from(block))
+            .and_then(|&start| dirs.1.get(start as usize + usize::from(offset)..))
+            .and_then(|listing| listing.get(..size as usize - 3))
+            .ok_or_else(corrupted)?;
+        while !listing.is_empty() {
+            let (count, start, base) = dir_header(listing, endian).ok_or_else(corrupted)?;
+            listing = &listing[12..];
+
+            for _ in 0..=count {
+                let (offset, delta, kind, name) =
+                    dir_entry(listing, endian).ok_or_else(corrupted)?;
+                listing = &listing[8 + name.len()..];
+
+                let number = base
+                    .checked_add_signed(i32::from(delta))
+                    .ok_or_else(corrupted)?;
+                let child = path.join(OsStr::from_bytes(name));
+                numbers.insert(child.clone(), number);
+                if matches!(kind, BASIC_DIRECTORY | EXTENDED_DIRECTORY) {
+                    let inode =
+                        inode_at(u64::from(start), usize::from(offset)).ok_or_else(corrupted)?;
+                    pending.push((child, inode));
+                }
+            }
+        }
+    }
+    Ok(numbers)
+}
+
+/// Returns where the listing of a directory inode is in the directory table: the
+/// offset of its metadata block, its offset within the block and its size. Returns
+/// None if the inode is not a directory or is truncated.
+fn dir_location(inode: &[u8], endian: Endian) -> Option<(u32, u16, u32)> {
+    // Every inode starts with its type, mode, uid, gid, mtime and number.
+    match read_u16(inode, 0, endian)? {
+        BASIC_DIRECTORY => Some((
+            read_u32(inode, 16, endian)?,
+            read_u16(inode, 26, endian)?,
+            u32::from(read_u16(inode, 24, endian)?),
+        )),
+        EXTENDED_DIRECTORY => Some((
+            read_u32(inode, 24, endian)?,
+            read_u16(inode, 34, endian)?,
+            read_u32(inode, 20, endian)?,
+        )),
+        _ => None,
+    }
+}
+
+/// Reads the header starting a run of directory entries: the number of entries
+/// minus one, the metadata block their inodes are in and the inode number they
+/// are relative to.
+fn dir_header(listing: &[u8], endian: Endian) -> Option<(u32, u32, u32)> {
+    Some((
+        read_u32(listing, 0, endian)?,
+        read_u32(listing, 4, endian)?,
+        read_u32(listing, 8, endian)?,
+    ))
+}
+
+/// Reads a directory entry: the offset of its inode within the metadata block, its
+/// inode number relative to the run's, its type and its name.
+fn dir_entry(listing: &[u8], endian: Endian) -> Option<(u16, i16, u16, &[u8])> {
+    let name_size = usize::from(read_u16(listing, 6, endian)?) + 1;
+    Some((
+        read_u16(listing, 0, endian)?,
+        read_u16(listing, 2, endian)? as i16,
+        read_u16(listing, 4, endian)?,
+        listing.get(8..8 + name_size)?,
+    ))
+}
+
+/// Reads the u16 at `at` in the given byte order, or returns None past the end of `bytes`.
+fn read_u16(bytes: &[u8], at: usize, endian: Endian) -> Option<u16> {
+    let bytes = bytes.get(at..at + 2)?.try_into().ok()?;
+    Some(match endian {
+        Endian::Little => u16::from_le_bytes(bytes),
+        Endian::Big => u16::from_be_bytes(bytes),
+    })
+}
+
+/// Reads the u32 at `at` in the given byte order, or returns None past the end of `bytes`.
+fn read_u32(bytes: &[u8], at: usize, endian: Endian) -> Option<u32> {
+    let bytes = bytes.get(at..at + 4)?.try_into().ok()?;
+    Some(match endian {
+        Endian::Little => u32::from_le_bytes(bytes),
+        Endian::Big => u32::from_be_bytes(bytes),
+    })
+}
+
+/// Returns the error for tables that don't match up.
+fn corrupted() -> SquishyError {
+    SquishyError::InvalidSquashFS("inconsistent inode or directory table".into())
+}
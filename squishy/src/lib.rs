@@ -1,21 +1,52 @@
 use std::{
-    collections::HashSet,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
     fs::{self, File, Permissions},
-    io::{BufReader, BufWriter, Read, Seek},
-    os::unix::fs::PermissionsExt,
-    path::{Path, PathBuf},
+    io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    time::{Duration, UNIX_EPOCH},
 };
 
-use backhand::{kind::Kind, BasicFile, FilesystemReader, InnerNode, NodeHeader};
+use backhand::{
+    compression::Compressor, kind::Kind, BasicFile, FilesystemReader, InnerNode, Node, NodeHeader,
+    SquashfsFileReader, SuperBlock,
+};
 use error::SquishyError;
+use scan::{
+    compressor_at, elf_end, is_valid_superblock, magic_at, metadata_blocks_fit, read_inode_table,
+    tables_at, Endian, MagicScanner,
+};
 
 #[cfg(feature = "rayon")]
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+#[cfg(feature = "hash")]
+use sha2::{Digest, Sha256};
+
+/// The backhand version squishy is built on, for use with [`SquashFS::inner`].
+pub use backhand;
 
 #[cfg(feature = "appimage")]
 pub mod appimage;
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod archive;
+#[cfg(feature = "tokio")]
+pub mod async_fs;
 
 pub mod error;
+mod extract;
+mod inode;
+mod scan;
+#[cfg(all(test, feature = "gzip"))]
+mod testutil;
+
+/// The default maximum number of symlinks followed when resolving, matching Linux's `MAXSYMLINKS`.
+pub const MAX_SYMLINK_HOPS: usize = 40;
 
 pub type Result<T> = std::result::Result<T, SquishyError>;
 
@@ -23,15 +54,45 @@ pub type Result<T> = std::result::Result<T, SquishyError>;
 /// It wraps a FilesystemReader, which is responsible for reading the contents of the SquashFS file.
 pub struct SquashFS<'a> {
     reader: FilesystemReader<'a>,
+    superblock: SuperBlock,
+    kind: ImageKind,
+    index: HashMap<PathBuf, usize>,
+    /// Inode number of each node
+    inodes: Vec<u32>,
+    /// Index of the first node, in path order, of each inode number
+    inode_index: HashMap<u32, usize>,
+    /// Index just past the last node under each node, see [`subtree_ends`]
+    subtree_ends: Vec<usize>,
+    /// Size of the buffer file contents are copied through, or None for the default
+    copy_buffer_size: Option<usize>,
 }
 
 /// The SquashFSEntry struct represents a single file or directory entry within the SquashFS filesystem.
-/// It contains information about the path, size, and type of the entry.
-#[derive(Debug)]
+/// It contains information about the path, size, ownership, permissions and type of the entry.
+///
+/// Entries are ordered by path, then by kind (files, directories, symlinks,
+/// block devices, char devices, fifos, sockets, unknown). Two entries are equal
+/// when they have the same path and the same kind of entry, which is enough to
+/// tell apart the entries of an image.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct SquashFSEntry<'a> {
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub header: NodeHeader,
     pub path: PathBuf,
-    pub size: u32,
+    /// Size in bytes, the full size even for files over 4 GiB, which can't be read
+    /// (see [`SquishyError::FileTooLarge`])
+    pub size: u64,
+    /// Unix permission bits of the entry
+    pub mode: u16,
+    /// Owner user id
+    pub uid: u32,
+    /// Owner group id
+    pub gid: u32,
+    /// Modification time, as absolute seconds since the Unix epoch (not relative to the superblock time)
+    pub mtime: u32,
+    /// Inode number, the same for paths that are hardlinks of each other
+    pub inode: u32,
     pub kind: EntryKind<'a>,
 }
 
@@ -40,268 +101,3473 @@ pub struct SquashFSEntry<'a> {
 pub enum EntryKind<'a> {
     File(&'a BasicFile),
     Directory,
+    /// Symlink with its target exactly as stored in the image, which may be
+    /// relative to the directory containing the symlink
     Symlink(PathBuf),
+    BlockDevice {
+        major: u32,
+        minor: u32,
+    },
+    CharDevice {
+        major: u32,
+        minor: u32,
+    },
+    Fifo,
+    Socket,
     Unknown,
 }
 
-impl<'a> SquashFS<'a> {
-    /// Creates a new SquashFS instance from a BufReader.
-    ///
-    /// # Arguments
-    /// * `reader` - A BufReader that provides access to the SquashFS data.
-    ///
-    /// # Returns
-    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
-    pub fn new<R>(mut reader: BufReader<R>, offset: Option<u64>) -> Result<Self>
-    where
-        R: Read + Seek + Send + 'a,
-    {
-        let offset = offset.unwrap_or(
-            Self::find_squashfs_offset(&mut reader).map_err(|_| SquishyError::NoSquashFsFound)?,
-        );
-        let reader = FilesystemReader::from_reader_with_offset(reader, offset)
-            .map_err(|e| SquishyError::InvalidSquashFS(e.to_string()))?;
+impl EntryKind<'_> {
+    /// Position of the kind when ordering entries.
+    fn rank(&self) -> u8 {
+        match self {
+            EntryKind::File(_) => 0,
+            EntryKind::Directory => 1,
+            EntryKind::Symlink(_) => 2,
+            EntryKind::BlockDevice { .. } => 3,
+            EntryKind::CharDevice { .. } => 4,
+            EntryKind::Fifo => 5,
+            EntryKind::Socket => 6,
+            EntryKind::Unknown => 7,
+        }
+    }
 
-        Ok(Self { reader })
+    /// Returns the short name of the kind, e.g. `file`, `dir` or `symlink`, without
+    /// the data it carries.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::File(_) => "file",
+            EntryKind::Directory => "dir",
+            EntryKind::Symlink(_) => "symlink",
+            EntryKind::BlockDevice { .. } => "block",
+            EntryKind::CharDevice { .. } => "char",
+            EntryKind::Fifo => "fifo",
+            EntryKind::Socket => "socket",
+            EntryKind::Unknown => "unknown",
+        }
     }
+}
 
-    /// Creates a new SquashFS instance from a file path. Tries to find offset automatically.
-    ///
-    /// # Arguments
-    /// * `path` - The path to the SquashFS file.
-    ///
-    /// # Returns
-    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
-    pub fn from_path<P: AsRef<Path>>(path: &'a P) -> Result<Self> {
-        let file = File::open(path).unwrap();
-        let reader = BufReader::new(file);
-        SquashFS::new(reader, None)
+/// Displays the short name of the kind, see [`EntryKind::as_str`], followed by the
+/// target for symlinks, e.g. `symlink -> usr/bin/app`.
+impl fmt::Display for EntryKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryKind::Symlink(target) => write!(f, "symlink -> {}", target.display()),
+            _ => f.write_str(self.as_str()),
+        }
     }
+}
 
-    /// Creates a new SquashFS instance from a file path.
-    ///
-    /// # Arguments
-    /// * `path` - The path to the SquashFS file.
-    /// * `offset` - Seek to offset before reading
-    ///
-    /// # Returns
-    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
-    pub fn from_path_with_offset<P: AsRef<Path>>(path: &'a P, offset: u64) -> Result<Self> {
-        let file = File::open(path).unwrap();
-        let reader = BufReader::new(file);
-        SquashFS::new(reader, Some(offset))
+/// The EntryKindFilter enum selects entries by kind regardless of the data their
+/// [`EntryKind`] carries, see [`SquashFS::entries_of_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKindFilter {
+    File,
+    Directory,
+    /// Symlinks, whatever their target
+    AnySymlink,
+    BlockDevice,
+    CharDevice,
+    Fifo,
+    Socket,
+}
+
+impl EntryKindFilter {
+    /// Returns true if the kind is selected by this filter.
+    pub fn matches(&self, kind: &EntryKind) -> bool {
+        matches!(
+            (self, kind),
+            (EntryKindFilter::File, EntryKind::File(_))
+                | (EntryKindFilter::Directory, EntryKind::Directory)
+                | (EntryKindFilter::AnySymlink, EntryKind::Symlink(_))
+                | (EntryKindFilter::BlockDevice, EntryKind::BlockDevice { .. })
+                | (EntryKindFilter::CharDevice, EntryKind::CharDevice { .. })
+                | (EntryKindFilter::Fifo, EntryKind::Fifo)
+                | (EntryKindFilter::Socket, EntryKind::Socket)
+        )
     }
 
-    /// Finds the starting offset of the SquashFS data within the input file.
-    ///
-    /// # Arguments
-    /// * `file` - The BufReader that provides access to the input file.
-    ///
-    /// # Returns
-    /// The starting offset of the SquashFS data, or an error if the SquashFS data is not found.
-    fn find_squashfs_offset<R>(file: &mut BufReader<R>) -> Result<u64>
-    where
-        R: Read + Seek,
-    {
-        let mut magic = [0_u8; 4];
-        let kind = Kind::from_target("le_v4_0").unwrap();
-        while file.read_exact(&mut magic).is_ok() {
-            if magic == kind.magic() {
-                let found = file.stream_position()? - magic.len() as u64;
-                file.rewind()?;
-                return Ok(found);
-            }
+    /// Returns the short name of the kinds selected, the same as [`EntryKind::as_str`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKindFilter::File => "file",
+            EntryKindFilter::Directory => "dir",
+            EntryKindFilter::AnySymlink => "symlink",
+            EntryKindFilter::BlockDevice => "block",
+            EntryKindFilter::CharDevice => "char",
+            EntryKindFilter::Fifo => "fifo",
+            EntryKindFilter::Socket => "socket",
         }
-        Err(SquishyError::NoSquashFsFound)
     }
+}
 
-    /// Returns an iterator over all the entries in the SquashFS filesystem.
-    pub fn entries(&self) -> impl Iterator<Item = SquashFSEntry> + '_ {
-        self.reader.files().map(|node| {
-            let size = match &node.inner {
-                InnerNode::File(file) => file.basic.file_size,
-                _ => 0,
-            };
+impl fmt::Display for EntryKindFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
-            let kind = match &node.inner {
-                InnerNode::File(file) => EntryKind::File(&file.basic),
-                InnerNode::Dir(_) => EntryKind::Directory,
-                InnerNode::Symlink(symlink) => EntryKind::Symlink(
-                    PathBuf::from(format!("/{}", symlink.link.display())).clone(),
-                ),
-                _ => EntryKind::Unknown,
-            };
+/// Parses the short name of a kind, as given by [`EntryKindFilter::as_str`], e.g. for
+/// a `--kind symlink` flag.
+impl FromStr for EntryKindFilter {
+    type Err = SquishyError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "file" => Ok(EntryKindFilter::File),
+            "dir" => Ok(EntryKindFilter::Directory),
+            "symlink" => Ok(EntryKindFilter::AnySymlink),
+            "block" => Ok(EntryKindFilter::BlockDevice),
+            "char" => Ok(EntryKindFilter::CharDevice),
+            "fifo" => Ok(EntryKindFilter::Fifo),
+            "socket" => Ok(EntryKindFilter::Socket),
+            _ => Err(SquishyError::UnknownEntryKind(s.to_owned())),
+        }
+    }
+}
 
-            SquashFSEntry {
-                header: node.header,
-                path: node.fullpath.clone(),
-                size,
-                kind,
+/// Serializes the kind as a tagged object, e.g. `{"type":"symlink","target":"/x"}`.
+/// Only serialization is supported, as file entries borrow from the SquashFS they came from.
+#[cfg(feature = "serde")]
+impl Serialize for EntryKind<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("EntryKind", 3)?;
+        match self {
+            EntryKind::File(_) => state.serialize_field("type", "file")?,
+            EntryKind::Directory => state.serialize_field("type", "directory")?,
+            EntryKind::Symlink(target) => {
+                state.serialize_field("type", "symlink")?;
+                state.serialize_field("target", target)?;
             }
-        })
+            EntryKind::BlockDevice { major, minor } => {
+                state.serialize_field("type", "block_device")?;
+                state.serialize_field("major", major)?;
+                state.serialize_field("minor", minor)?;
+            }
+            EntryKind::CharDevice { major, minor } => {
+                state.serialize_field("type", "char_device")?;
+                state.serialize_field("major", major)?;
+                state.serialize_field("minor", minor)?;
+            }
+            EntryKind::Fifo => state.serialize_field("type", "fifo")?,
+            EntryKind::Socket => state.serialize_field("type", "socket")?,
+            EntryKind::Unknown => state.serialize_field("type", "unknown")?,
+        }
+        state.end()
     }
+}
 
-    #[cfg(feature = "rayon")]
-    /// Returns a parallel iterator over all the entries in the SquashFS filesystem.
-    pub fn par_entries(&self) -> impl ParallelIterator<Item = SquashFSEntry> + '_ {
-        self.reader
-            .files()
-            .map(|node| {
-                let size = match &node.inner {
-                    InnerNode::File(file) => file.basic.file_size,
-                    _ => 0,
-                };
-
-                let kind = match &node.inner {
-                    InnerNode::File(file) => EntryKind::File(&file.basic),
-                    InnerNode::Dir(_) => EntryKind::Directory,
-                    InnerNode::Symlink(symlink) => EntryKind::Symlink(
-                        PathBuf::from(format!("/{}", symlink.link.display())).clone(),
-                    ),
-                    _ => EntryKind::Unknown,
-                };
-
-                SquashFSEntry {
-                    header: node.header,
-                    path: node.fullpath.clone(),
-                    size,
-                    kind,
-                }
-            })
-            .collect::<Vec<SquashFSEntry>>()
-            .into_par_iter()
+/// The Compression enum represents the compression algorithm used for the data in the SquashFS filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Lzma,
+    Lzo,
+    Xz,
+    Lz4,
+    Zstd,
+}
+
+impl From<Compressor> for Compression {
+    fn from(compressor: Compressor) -> Self {
+        match compressor {
+            Compressor::None => Compression::None,
+            Compressor::Gzip => Compression::Gzip,
+            Compressor::Lzma => Compression::Lzma,
+            Compressor::Lzo => Compression::Lzo,
+            Compressor::Xz => Compression::Xz,
+            Compressor::Lz4 => Compression::Lz4,
+            Compressor::Zstd => Compression::Zstd,
+        }
     }
+}
 
-    /// Returns an iterator over all the entries in the SquashFS filesystem
-    /// that match the provided predicate function.
+/// The ImageKind enum represents the on-disk flavour of a SquashFS v4 image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    /// Little-endian image, as built by a stock mksquashfs
+    LittleEndian,
+    /// Big-endian image
+    BigEndian,
+    /// AVM Fritz!OS image, with big-endian metadata and little-endian data
+    AvmBigEndian,
+}
+
+impl ImageKind {
+    /// Returns the backhand kind used to read images of this flavour.
+    fn to_kind(self) -> Kind {
+        let target = match self {
+            ImageKind::LittleEndian => "le_v4_0",
+            ImageKind::BigEndian => "be_v4_0",
+            ImageKind::AvmBigEndian => "avm_be_v4_0",
+        };
+        Kind::from_target(target).unwrap()
+    }
+
+    /// Returns the byte order of the superblock, inodes and directory entries.
+    fn type_endian(self) -> Endian {
+        match self {
+            ImageKind::LittleEndian => Endian::Little,
+            ImageKind::BigEndian | ImageKind::AvmBigEndian => Endian::Big,
+        }
+    }
+
+    /// Returns the byte order of the metadata block headers and data.
+    fn data_endian(self) -> Endian {
+        match self {
+            ImageKind::LittleEndian | ImageKind::AvmBigEndian => Endian::Little,
+            ImageKind::BigEndian => Endian::Big,
+        }
+    }
+}
+
+/// The SuperblockInfo struct contains image-level metadata read from the SquashFS superblock.
+#[derive(Debug, Clone)]
+pub struct SuperblockInfo {
+    /// Size of a data block in bytes
+    pub block_size: u32,
+    /// Number of inodes stored in the image
+    pub inode_count: u32,
+    /// Number of entries in the fragment table
+    pub fragment_count: u32,
+    /// Time the image was created, in seconds since the Unix epoch
+    pub creation_time: u32,
+    /// Compression algorithm used for the data
+    pub compression: Compression,
+}
+
+/// The EntryCounts struct holds the number of entries of each kind in a SquashFS filesystem.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryCounts {
+    pub files: usize,
+    pub directories: usize,
+    pub symlinks: usize,
+    pub block_devices: usize,
+    pub char_devices: usize,
+    pub fifos: usize,
+    pub sockets: usize,
+}
+
+/// The FileLayout struct describes how a regular file is stored in the image, see [`SquashFS::file_layout`].
+///
+/// Files are split into blocks of the image's block size, each compressed on its
+/// own. The tail of a file that doesn't fill a whole block may instead be packed,
+/// with the tails of other files, into a shared fragment block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLayout {
+    /// Uncompressed size of the file in bytes
+    pub size: u64,
+    /// Block size of the image in bytes
+    pub block_size: u32,
+    /// Number of data blocks, including sparse ones
+    pub blocks: usize,
+    /// Number of sparse blocks, all zeros and not stored in the image
+    pub sparse_blocks: usize,
+    /// Number of data blocks stored uncompressed, as compressing didn't make them smaller
+    pub uncompressed_blocks: usize,
+    /// Size in bytes the data blocks take up in the image
+    pub stored_size: u64,
+    /// Index of the fragment block holding the tail of the file, if any
+    pub fragment_index: Option<u32>,
+    /// Uncompressed size in bytes of the tail stored in the fragment block
+    pub fragment_size: u64,
+}
+
+/// The VerifyReport struct holds the outcome of [`SquashFS::verify`].
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Number of regular files read
+    pub files: usize,
+    /// Number of decompressed bytes read across all files
+    pub bytes: u64,
+    /// Files that couldn't be fully read, with the error for each
+    pub errors: Vec<(PathBuf, SquishyError)>,
+}
+
+impl VerifyReport {
+    /// Returns true if every file was read without errors.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// The SymlinkResolution enum tells how a symlink chain resolves, see [`SquashFS::check_symlink`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymlinkResolution<'a> {
+    /// The chain ends at this entry, which is not a symlink
+    Resolved(SquashFSEntry<'a>),
+    /// The chain points at this path, which doesn't exist in the image
+    Dangling(PathBuf),
+    /// The chain ends at a symlink pointing outside the image, with this target as
+    /// stored: an absolute path missing from the image, like `/etc/resolv.conf`, or
+    /// a relative one going above the root
+    External(PathBuf),
+    /// The entry is not a symlink
+    NotSymlink,
+}
+
+/// The WalkAction enum tells [`SquashFS::walk`] how to continue after visiting an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkAction {
+    /// Keep walking
+    Continue,
+    /// Don't descend into the current directory, no-op for other entries
+    SkipDir,
+    /// Stop walking
+    Stop,
+}
+
+/// The ExtractOptions struct configures how [`SquashFS::extract_all_with_options`] writes entries.
+///
+/// By default it behaves like [`SquashFS::extract_all`].
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractOptions {
+    hardlinks: bool,
+    devices: bool,
+    on_error: OnError,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            hardlinks: true,
+            devices: false,
+            on_error: OnError::Abort,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Creates extraction options with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether files that are hardlinks of each other in the image, i.e. share an
+    /// inode, are extracted as hardlinks of the first one written. Enabled by default,
+    /// disable it to write separate copies, e.g. on filesystems without hardlinks.
+    ///
+    /// Files that merely have the same contents, which mksquashfs stores only once,
+    /// have inodes of their own and are always written as separate files.
     ///
     /// # Arguments
-    /// * `predicate` - A function that takes a &Path and returns a bool, indicating whether the entry should be included.
-    pub fn find_entries<F>(&self, predicate: F) -> impl Iterator<Item = SquashFSEntry> + '_
-    where
-        F: Fn(&Path) -> bool + 'a,
-    {
-        self.entries().filter(move |entry| predicate(&entry.path))
+    /// * `hardlinks` - Whether to create hardlinks.
+    pub fn hardlinks(mut self, hardlinks: bool) -> Self {
+        self.hardlinks = hardlinks;
+        self
     }
 
-    /// Reads the contents of the specified file from the SquashFS filesystem.
+    /// Sets whether block and character devices and fifos are recreated with `mknod`,
+    /// instead of being skipped. Disabled by default.
+    ///
+    /// Creating device nodes requires root, or the `CAP_MKNOD` capability on Linux.
+    /// Without it, each device fails with a permission error, handled as set with
+    /// [`ExtractOptions::on_error`]: with [`OnError::Skip`] they are recorded in the
+    /// [`ExtractReport`] while everything else is extracted.
     ///
     /// # Arguments
-    /// * `path` - The path to the file within the SquashFS filesystem.
+    /// * `devices` - Whether to create device nodes and fifos, e.g. for root filesystem images.
+    pub fn devices(mut self, devices: bool) -> Self {
+        self.devices = devices;
+        self
+    }
+
+    /// Sets what to do when an entry cannot be extracted. Defaults to [`OnError::Abort`].
     ///
-    /// # Returns
-    /// The contents of the file as a Vec<u8>, or an error if the file is not found.
-    pub fn read_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
-        let path = path.as_ref();
+    /// # Arguments
+    /// * `on_error` - Whether to stop, or to skip the entry and carry on with the others.
+    pub fn on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+}
 
-        for node in self.reader.files() {
-            if node.fullpath == path {
-                if let InnerNode::File(file) = &node.inner {
-                    let mut reader = self.reader.file(&file.basic).reader().bytes();
-                    let mut contents = Vec::new();
+/// The OnError enum tells the extraction what to do when an entry cannot be extracted,
+/// see [`ExtractOptions::on_error`] and [`SquashFS::extract_all_with_error_handler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnError {
+    /// Stop and return the error
+    Abort,
+    /// Record the error in the [`ExtractReport`] and continue with the next entry
+    Skip,
+}
 
-                    while let Some(Ok(byte)) = reader.next() {
-                        contents.push(byte);
-                    }
+/// The ExtractReport struct holds the outcome of [`SquashFS::extract_all_with_report`].
+#[derive(Debug, Default)]
+pub struct ExtractReport {
+    /// Number of entries written. Sockets are always skipped, and device nodes and
+    /// fifos unless [`ExtractOptions::devices`] is set
+    pub extracted: usize,
+    /// Entries that couldn't be extracted, with the error for each
+    pub failures: Vec<(PathBuf, SquishyError)>,
+}
 
-                    return Ok(contents);
-                }
+impl ExtractReport {
+    /// Returns true if every entry was extracted without errors.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The DirNode struct is an entry of the directory hierarchy built by [`SquashFS::tree`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DirNode<'a> {
+    /// The entry itself
+    pub entry: SquashFSEntry<'a>,
+    /// Entries directly inside this one, sorted by name. Empty for anything but directories
+    pub children: Vec<DirNode<'a>>,
+}
+
+/// The ReadDir struct is an iterator over the entries of a directory, see [`SquashFS::read_dir_iter`].
+pub struct ReadDir<'a> {
+    nodes: &'a [Node<SquashfsFileReader>],
+    inodes: &'a [u32],
+    subtree_ends: &'a [usize],
+    /// Index of the next child
+    next: usize,
+    /// Index just past the directory's subtree
+    end: usize,
+    block_size: u32,
+}
+
+impl<'a> Iterator for ReadDir<'a> {
+    type Item = SquashFSEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+        let entry = SquashFSEntry::from_node(
+            &self.nodes[self.next],
+            self.inodes[self.next],
+            self.block_size,
+        );
+        self.next = self.subtree_ends[self.next];
+        Some(entry)
+    }
+}
+
+/// The SeekableFile struct reads a file of the SquashFS filesystem with random access,
+/// see [`SquashFS::open_file_seek`].
+///
+/// Blocks are decompressed one at a time as they are read, and the last one is kept,
+/// so seeking within it is free. Seeking anywhere else, backwards included, costs
+/// decompressing the block the new position falls in once it is read.
+pub struct SeekableFile<'s, 'a> {
+    squashfs: &'s SquashFS<'a>,
+    file: &'s BasicFile,
+    size: u64,
+    position: u64,
+    /// Offset in the image of each data block
+    block_starts: Vec<u64>,
+    /// Index of the block held in `block`, the fragment following the last data block
+    block_index: Option<usize>,
+    block: Vec<u8>,
+}
+
+impl SeekableFile<'_, '_> {
+    /// Decompresses the given block of the file into `block`. Sparse blocks are read as zeros.
+    fn load_block(&mut self, index: usize) -> std::io::Result<()> {
+        let block_size = u64::from(self.squashfs.reader.block_size);
+        let len = block_size.min(self.size - index as u64 * block_size);
+        self.block_index = None;
+        self.block.clear();
+
+        let basic = match self.file.block_sizes.get(index) {
+            Some(block) if block.size() == 0 => {
+                self.block.resize(len as usize, 0);
+                self.block_index = Some(index);
+                return Ok(());
             }
+            Some(block) => BasicFile {
+                blocks_start: self.block_starts[index] as u32,
+                frag_index: 0xffffffff,
+                block_offset: 0,
+                file_size: len as u32,
+                block_sizes: vec![*block],
+            },
+            None => BasicFile {
+                blocks_start: self.file.blocks_start,
+                frag_index: self.file.frag_index,
+                block_offset: self.file.block_offset,
+                file_size: len as u32,
+                block_sizes: Vec::new(),
+            },
+        };
+        self.squashfs
+            .reader
+            .file(&basic)
+            .reader()
+            .read_to_end(&mut self.block)?;
+        if self.block.len() as u64 != len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.block_index = Some(index);
+        Ok(())
+    }
+}
+
+impl Read for SeekableFile<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let block_size = u64::from(self.squashfs.reader.block_size);
+        let index = (self.position / block_size) as usize;
+        if self.block_index != Some(index) {
+            self.load_block(index)?;
         }
 
-        Err(SquishyError::FileNotFound(path.to_path_buf()))
+        let start = (self.position - index as u64 * block_size) as usize;
+        let available = &self.block[start..];
+        let read = buf.len().min(available.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read as u64;
+        Ok(read)
     }
+}
 
-    /// Writes the contents of the specified file from the SquashFS filesystem
-    /// to the specified destination path.
+impl Seek for SeekableFile<'_, '_> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// The NodeHandle struct is an entry already located with [`SquashFS::resolve_node`].
+///
+/// It can be passed to [`SquashFS::read_file`], [`SquashFS::read_file_to_writer`] or
+/// [`SquashFS::open_file`] in place of a path, skipping the lookup.
+#[derive(Clone, Copy)]
+pub struct NodeHandle<'a> {
+    nodes: &'a [Node<SquashfsFileReader>],
+    inodes: &'a [u32],
+    index: usize,
+    block_size: u32,
+}
+
+impl<'a> NodeHandle<'a> {
+    /// Returns the full path of the entry.
+    pub fn path(&self) -> &'a Path {
+        &self.nodes[self.index].fullpath
+    }
+
+    /// Returns the entry the handle points to.
+    pub fn entry(&self) -> SquashFSEntry<'a> {
+        SquashFSEntry::from_node(
+            &self.nodes[self.index],
+            self.inodes[self.index],
+            self.block_size,
+        )
+    }
+}
+
+impl fmt::Debug for NodeHandle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NodeHandle").field(&self.path()).finish()
+    }
+}
+
+/// The EntryLocator trait is anything an entry can be looked up by: a path, or a
+/// [`NodeHandle`] from the same filesystem.
+pub trait EntryLocator {
+    /// Returns the path of the entry, as reported in errors.
+    fn entry_path(&self) -> &Path;
+
+    /// Returns the index of the entry's node in the filesystem, if it is found there.
+    fn node_index(&self, squashfs: &SquashFS<'_>) -> Option<usize>;
+}
+
+impl<P: AsRef<Path>> EntryLocator for P {
+    fn entry_path(&self) -> &Path {
+        self.as_ref()
+    }
+
+    fn node_index(&self, squashfs: &SquashFS<'_>) -> Option<usize> {
+        squashfs.node_index(self.as_ref())
+    }
+}
+
+impl EntryLocator for NodeHandle<'_> {
+    fn entry_path(&self) -> &Path {
+        self.path()
+    }
+
+    fn node_index(&self, squashfs: &SquashFS<'_>) -> Option<usize> {
+        std::ptr::eq(self.nodes, squashfs.reader.root.nodes.as_slice()).then_some(self.index)
+    }
+}
+
+/// The SquashFSBuilder struct configures how a SquashFS image is opened, see [`SquashFS::builder`].
+///
+/// By default it behaves like [`SquashFS::new`]: the whole reader is scanned for
+/// the SquashFS data and its flavour is detected from the magic bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SquashFSBuilder {
+    offset: Option<u64>,
+    scan_start: Option<u64>,
+    max_scan: Option<u64>,
+    kind: Option<ImageKind>,
+    read_buffer_size: Option<usize>,
+}
+
+impl SquashFSBuilder {
+    /// Creates a builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the offset of the SquashFS data, so no scanning is done.
     ///
     /// # Arguments
-    /// * `file` - The basic file within the SquashFS filesystem.
-    /// * `dest` - The destination path to write the file to.
+    /// * `offset` - Offset of the SquashFS data within the reader.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Starts scanning for the SquashFS data at the given offset, e.g. where it was
+    /// found in a similar file. If nothing is found from there, the whole reader is
+    /// scanned as usual. Ignored when an offset is set.
     ///
-    /// # Returns
-    /// An empty result, or an error if the file cannot be read or written.
-    pub fn write_file<P: AsRef<Path>>(&self, file: &BasicFile, dest: P) -> Result<()> {
-        let output_file = File::create(dest)?;
-        let mut writer = BufWriter::with_capacity(file.file_size as usize, &output_file);
-        let file = self.reader.file(file);
-        let mut reader = file.reader();
-        std::io::copy(&mut reader, &mut writer)?;
-        Ok(())
+    /// # Arguments
+    /// * `start` - Offset within the reader to start scanning from.
+    pub fn scan_start(mut self, start: u64) -> Self {
+        self.scan_start = Some(start);
+        self
     }
 
-    /// Writes the contents of the specified file from the SquashFS filesystem
-    /// to the specified destination path with permissions.
+    /// Limits the scan for the SquashFS data to the first `max_scan` bytes, see
+    /// [`SquashFS::new_with_scan_limit`]. With [`SquashFSBuilder::scan_start`], the
+    /// first scan is limited to `max_scan` bytes from the start offset. Ignored when
+    /// an offset is set.
     ///
     /// # Arguments
-    /// * `file` - The basic file within the SquashFS filesystem.
-    /// * `dest` - The destination path to write the file to.
-    /// * `header` - Node header containing file information.
+    /// * `max_scan` - How far into the reader the SquashFS data may start.
+    pub fn max_scan(mut self, max_scan: u64) -> Self {
+        self.max_scan = Some(max_scan);
+        self
+    }
+
+    /// Reads the image as the given flavour instead of detecting it from the magic bytes.
     ///
-    /// # Returns
-    /// An empty result, or an error if the file cannot be read or written.
-    pub fn write_file_with_permissions<P: AsRef<Path>>(
-        &self,
-        file: &BasicFile,
-        dest: P,
-        header: NodeHeader,
-    ) -> Result<()> {
-        let output_file = File::create(&dest)?;
-        let mode = u32::from(header.permissions);
-        fs::set_permissions(dest, Permissions::from_mode(mode))?;
-        let mut writer = BufWriter::with_capacity(file.file_size as usize, &output_file);
-        let file = self.reader.file(file);
-        let mut reader = file.reader();
-        std::io::copy(&mut reader, &mut writer)?;
-        Ok(())
+    /// # Arguments
+    /// * `kind` - The flavour of the SquashFS image.
+    pub fn kind(mut self, kind: ImageKind) -> Self {
+        self.kind = Some(kind);
+        self
     }
 
-    /// Resolves the symlink chain starting from the specified entry,
-    /// returning the final target entry or an error if a cycle is detected.
+    /// Sets the size of the buffer the image is read through with
+    /// [`SquashFSBuilder::open_path`], and of the buffer file contents are copied
+    /// through by [`SquashFS::read_file_to_writer`], the `write_file` methods and
+    /// extraction. Readers given to [`SquashFSBuilder::open_reader`] keep their own buffer.
+    ///
+    /// The image and copy buffers default to 8 KiB, the extraction buffer to 64 KiB.
+    /// Decompression dominates the cost of reading files, so a bigger buffer mostly
+    /// helps when the image sits on slow or remote storage.
     ///
     /// # Arguments
-    /// * `entry` - The entry to resolve the symlink for.
+    /// * `size` - The buffer size in bytes, a size of 0 is taken as 1.
+    pub fn read_buffer_size(mut self, size: usize) -> Self {
+        self.read_buffer_size = Some(size.max(1));
+        self
+    }
+
+    /// Opens the SquashFS image in the file at the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SquashFS file.
     ///
     /// # Returns
-    /// The final target entry, or None if the entry is not a symlink, or an error if a cycle is detected.
-    pub fn resolve_symlink(&self, entry: &SquashFSEntry) -> Result<Option<SquashFSEntry>> {
-        match &entry.kind {
-            EntryKind::Symlink(target) => {
-                let mut visited = HashSet::new();
-                visited.insert(entry.path.clone());
-                self.follow_symlink(target, &mut visited)
-            }
-            _ => Ok(None),
-        }
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn open_path<'a, P: AsRef<Path>>(self, path: P) -> Result<SquashFS<'a>> {
+        let file = File::open(path)?;
+        let reader = match self.read_buffer_size {
+            Some(size) => BufReader::with_capacity(size, file),
+            None => BufReader::new(file),
+        };
+        self.open_reader(reader)
     }
 
-    /// Recursively follows symlinks, keeping track of the visited paths
-    /// to detect and report cycles.
+    /// Opens the SquashFS image in the reader.
     ///
     /// # Arguments
-    /// * `target` - The path to the symlink target.
-    /// * `visited` - A mutable HashSet to keep track of visited paths.
+    /// * `reader` - A BufReader that provides access to the SquashFS data.
     ///
     /// # Returns
-    /// The final target entry, or an error if a cycle is detected.
-    fn follow_symlink(
-        &self,
-        target: &Path,
-        visited: &mut HashSet<PathBuf>,
-    ) -> Result<Option<SquashFSEntry>> {
-        if !visited.insert(target.to_path_buf()) {
-            return Err(SquishyError::SymlinkError("Cyclic symlink detected".into()));
-        }
-
-        let target_path = target.to_path_buf();
-
-        if let Some(target_entry) = self.find_entries(move |p| p == target_path).next() {
-            match &target_entry.kind {
-                EntryKind::Symlink(next_target) => self.follow_symlink(next_target, visited),
-                _ => Ok(Some(target_entry)),
-            }
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn open_reader<'a, R>(self, mut reader: BufReader<R>) -> Result<SquashFS<'a>>
+    where
+        R: Read + Seek + Send + 'a,
+    {
+        let hinted = match (self.offset, self.scan_start) {
+            (None, Some(start)) => {
+                reader.seek(SeekFrom::Start(start))?;
+                SquashFS::find_squashfs_offset(&mut reader, self.max_scan).ok()
+            }
+            _ => None,
+        };
+        let offset = match self.offset.or(hinted) {
+            Some(offset) => offset,
+            None => {
+                reader.rewind()?;
+                SquashFS::find_squashfs_offset(&mut reader, self.max_scan)
+                    .map_err(|_| SquishyError::NoSquashFsFound)?
+            }
+        };
+        let mut squashfs = SquashFS::open_at(reader, offset, self.kind)?;
+        squashfs.copy_buffer_size = self.read_buffer_size;
+        Ok(squashfs)
+    }
+}
+
+impl<'a> SquashFS<'a> {
+    /// Returns a builder to configure how the SquashFS image is opened.
+    pub fn builder() -> SquashFSBuilder {
+        SquashFSBuilder::new()
+    }
+
+    /// Creates a new SquashFS instance from a BufReader.
+    ///
+    /// # Arguments
+    /// * `reader` - A BufReader that provides access to the SquashFS data.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn new<R>(mut reader: BufReader<R>, offset: Option<u64>) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'a,
+    {
+        let offset = match offset {
+            Some(offset) => offset,
+            None => Self::find_squashfs_offset(&mut reader, None)
+                .map_err(|_| SquishyError::NoSquashFsFound)?,
+        };
+        Self::from_reader_at_offset(reader, offset)
+    }
+
+    /// Creates a new SquashFS instance from a BufReader, giving up on finding the
+    /// SquashFS magic bytes once the first `max_scan_bytes` bytes have been scanned.
+    ///
+    /// This keeps opening a large file that contains no SquashFS data from
+    /// reading all of it, e.g. AppImages have their SquashFS data within the first few MB.
+    ///
+    /// # Arguments
+    /// * `reader` - A BufReader that provides access to the SquashFS data.
+    /// * `max_scan_bytes` - How far into the reader the SquashFS data may start.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn new_with_scan_limit<R>(mut reader: BufReader<R>, max_scan_bytes: u64) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'a,
+    {
+        let offset = Self::find_squashfs_offset(&mut reader, Some(max_scan_bytes))
+            .map_err(|_| SquishyError::NoSquashFsFound)?;
+        Self::from_reader_at_offset(reader, offset)
+    }
+
+    /// Creates a new SquashFS instance from a BufReader when the offset is already known.
+    /// No scanning for the SquashFS magic bytes is done.
+    ///
+    /// The image flavour is detected from the magic bytes at the offset: little-endian
+    /// images start with `hsqs`, big-endian ones with `sqsh`. Big-endian images whose
+    /// inode table doesn't read with big-endian metadata block headers are opened as
+    /// AVM images.
+    ///
+    /// # Arguments
+    /// * `reader` - A BufReader that provides access to the SquashFS data.
+    /// * `offset` - Offset of the SquashFS data within the reader.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data at the offset is valid, or an error if it is not.
+    pub fn from_reader_at_offset<R>(reader: BufReader<R>, offset: u64) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'a,
+    {
+        Self::open_at(reader, offset, None)
+    }
+
+    /// Opens the SquashFS data at the offset, as the given flavour or the one detected
+    /// from the magic bytes if None, see [`SquashFS::from_reader_at_offset`].
+    fn open_at<R>(mut reader: BufReader<R>, offset: u64, kind: Option<ImageKind>) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'a,
+    {
+        let magic = magic_at(&mut reader, offset)?;
+        if let Some(endian) = magic {
+            match compressor_at(&mut reader, offset, endian)? {
+                Some(compressor) if !is_compressor_enabled(compressor) => {
+                    return Err(SquishyError::UnsupportedCompression(compressor));
+                }
+                _ => {}
+            }
+        }
+
+        // The raw inode table is read with the magic's byte order, which is the byte
+        // order of the inodes for every flavour.
+        let inode_table = match magic {
+            Some(endian) => match tables_at(&mut reader, offset, endian)? {
+                Some(tables) => read_inode_table(&mut reader, offset, tables)?,
+                None => None,
+            },
+            None => None,
+        };
+        let kind = match (kind, magic) {
+            (Some(kind), _) => kind,
+            // AVM images have a big-endian superblock too, only their metadata block
+            // headers and data are little-endian.
+            (None, Some(Endian::Big)) => match &inode_table {
+                Some(table) if !metadata_blocks_fit(table, Endian::Big) => ImageKind::AvmBigEndian,
+                _ => ImageKind::BigEndian,
+            },
+            (None, _) => ImageKind::LittleEndian,
+        };
+
+        let squashfs =
+            backhand::Squashfs::from_reader_with_offset_and_kind(reader, offset, kind.to_kind())?;
+        let superblock = squashfs.superblock;
+        // backhand checked the table offsets against the image, so the table was read.
+        let inode_table = inode_table.unwrap_or_default();
+        let numbers = inode::inode_numbers(
+            &inode::decompress_table(&inode_table, kind.data_endian(), superblock.compressor)?,
+            &squashfs.dir_blocks,
+            superblock.root_inode,
+            kind.type_endian(),
+        )?;
+        let reader = squashfs.into_filesystem_reader()?;
+        let index = reader
+            .files()
+            .enumerate()
+            .map(|(idx, node)| (node.fullpath.clone(), idx))
+            .collect();
+        let inodes = reader
+            .files()
+            .map(|node| {
+                numbers.get(&node.fullpath).copied().ok_or_else(|| {
+                    SquishyError::InvalidSquashFS(format!(
+                        "no inode number for {}",
+                        node.fullpath.display()
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut inode_index = HashMap::new();
+        for (idx, &inode) in inodes.iter().enumerate() {
+            inode_index.entry(inode).or_insert(idx);
+        }
+        let subtree_ends = subtree_ends(&reader.root.nodes);
+
+        Ok(Self {
+            reader,
+            superblock,
+            kind,
+            index,
+            inodes,
+            inode_index,
+            subtree_ends,
+            copy_buffer_size: None,
+        })
+    }
+
+    /// Creates a new SquashFS instance from a file path. Tries to find offset automatically.
+    ///
+    /// The path is only used to open the file, so the returned instance doesn't
+    /// borrow it and can be a `SquashFS<'static>`.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SquashFS file.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        SquashFS::from_file(File::open(path)?)
+    }
+
+    /// Creates a new SquashFS instance from an already open file. Tries to find offset
+    /// automatically, scanning from the file's current position.
+    ///
+    /// # Arguments
+    /// * `file` - The open SquashFS file.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn from_file(file: File) -> Result<Self> {
+        SquashFS::new(BufReader::new(file), None)
+    }
+
+    /// Creates a new SquashFS instance from a file path.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SquashFS file.
+    /// * `offset` - Seek to offset before reading
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn from_path_with_offset<P: AsRef<Path>>(path: P, offset: u64) -> Result<Self> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        SquashFS::new(reader, Some(offset))
+    }
+
+    /// Creates a new SquashFS instance by memory-mapping the file at the given path.
+    /// Tries to find offset automatically.
+    ///
+    /// Reads then go through the OS page cache instead of `read` calls, which helps
+    /// when randomly reading many small files from a large image.
+    ///
+    /// The file must not be truncated or modified while the SquashFS instance is alive,
+    /// doing so is undefined behaviour. Use [`SquashFS::from_path`] if that can't be guaranteed.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SquashFS file.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    #[cfg(feature = "memmap2")]
+    pub fn from_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees the file isn't modified while it is mapped, as documented above.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        SquashFS::new(BufReader::new(Cursor::new(mmap)), None)
+    }
+
+    /// Creates a new SquashFS instance from an in-memory buffer. Tries to find offset automatically.
+    ///
+    /// # Arguments
+    /// * `data` - The bytes containing the SquashFS data.
+    ///
+    /// # Returns
+    /// A SquashFS instance if the SquashFS data is found and valid, or an error if it is not.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self> {
+        let reader = BufReader::new(Cursor::new(data));
+        SquashFS::new(reader, None)
+    }
+
+    /// Finds the starting offset of the SquashFS data within the input file.
+    /// Occurrences of the magic bytes that aren't followed by a plausible
+    /// superblock are skipped.
+    ///
+    /// When the input starts with an ELF file, as AppImages do, the scan starts at the
+    /// end of the ELF so magic-like bytes within it are never picked, and only falls
+    /// back to scanning the ELF itself if nothing is found after it.
+    ///
+    /// # Arguments
+    /// * `file` - The reader that provides access to the input file.
+    /// * `max_scan` - How many bytes to scan before giving up, or None to scan the whole file.
+    ///
+    /// # Returns
+    /// The starting offset of the SquashFS data, or an error if the SquashFS data is not found.
+    fn find_squashfs_offset<R>(file: &mut R, max_scan: Option<u64>) -> Result<u64>
+    where
+        R: Read + Seek,
+    {
+        let start = file.stream_position()?;
+        if let Some(elf_end) = elf_end(file, start)? {
+            let skipped = elf_end - start;
+            if max_scan.is_none_or(|max_scan| skipped < max_scan) {
+                file.seek(SeekFrom::Start(elf_end))?;
+                let max_scan = max_scan.map(|max_scan| max_scan - skipped);
+                if let Ok(found) = Self::scan_for_superblock(file, max_scan) {
+                    return Ok(found);
+                }
+                file.seek(SeekFrom::Start(start))?;
+            }
+        }
+        Self::scan_for_superblock(file, max_scan)
+    }
+
+    /// Scans the input file from its current position for magic bytes followed by
+    /// a plausible superblock, see [`SquashFS::find_squashfs_offset`].
+    fn scan_for_superblock<R>(file: &mut R, max_scan: Option<u64>) -> Result<u64>
+    where
+        R: Read + Seek,
+    {
+        let mut scanner = MagicScanner::new(file)?;
+        if let Some(max_scan) = max_scan {
+            scanner = scanner.with_limit(max_scan);
+        }
+        while let Some((found, _)) = scanner.next_offset(file)? {
+            if is_valid_superblock(file, found)? {
+                file.rewind()?;
+                return Ok(found);
+            }
+        }
+        Err(SquishyError::NoSquashFsFound)
+    }
+
+    /// Checks whether the reader contains a SquashFS image, without building the filesystem.
+    /// Only the offset scan and superblock check are run, and the reader is left at the
+    /// position it was at.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to scan, from its start.
+    ///
+    /// # Returns
+    /// The offset of the first SquashFS image found, or None if there is none or the
+    /// reader cannot be read.
+    pub fn probe<R: Read + Seek>(reader: &mut R) -> Option<u64> {
+        let position = reader.stream_position().ok()?;
+        let offset = match reader.rewind() {
+            Ok(()) => Self::find_squashfs_offset(reader, None).ok(),
+            Err(_) => None,
+        };
+        reader.seek(SeekFrom::Start(position)).ok()?;
+        offset
+    }
+
+    /// Finds every offset within the reader where a SquashFS image starts.
+    /// Each occurrence of the magic bytes is checked to be a plausible superblock,
+    /// so magic bytes that merely appear inside data are skipped.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to scan, from its start.
+    ///
+    /// # Returns
+    /// The offsets of all SquashFS images found, in ascending order.
+    pub fn find_all_offsets<R: Read + Seek>(reader: &mut R) -> Result<Vec<u64>> {
+        reader.rewind()?;
+        let mut scanner = MagicScanner::new(reader)?;
+        let mut offsets = Vec::new();
+        while let Some((offset, _)) = scanner.next_offset(reader)? {
+            if is_valid_superblock(reader, offset)? {
+                offsets.push(offset);
+            }
+        }
+        reader.rewind()?;
+        Ok(offsets)
+    }
+
+    /// Returns the underlying backhand filesystem reader, for anything squishy doesn't expose.
+    ///
+    /// The reader's type comes from backhand, so relying on it ties you to the
+    /// backhand version squishy depends on, which may change in any release.
+    pub fn inner(&self) -> &FilesystemReader<'a> {
+        &self.reader
+    }
+
+    /// Consumes the SquashFS instance and returns the underlying backhand filesystem reader.
+    /// See [`SquashFS::inner`].
+    pub fn into_inner(self) -> FilesystemReader<'a> {
+        self.reader
+    }
+
+    /// Returns the flavour of the SquashFS image, e.g. whether it is big-endian.
+    pub fn kind(&self) -> ImageKind {
+        self.kind
+    }
+
+    /// Returns the compression algorithm used by the SquashFS filesystem.
+    pub fn compression(&self) -> Compression {
+        self.reader.compressor.into()
+    }
+
+    /// Returns image-level metadata from the SquashFS superblock.
+    pub fn superblock(&self) -> SuperblockInfo {
+        SuperblockInfo {
+            block_size: self.superblock.block_size,
+            inode_count: self.superblock.inode_count,
+            fragment_count: self.superblock.frag_count,
+            creation_time: self.superblock.mod_time,
+            compression: self.compression(),
+        }
+    }
+
+    /// Returns an iterator over all the entries in the SquashFS filesystem.
+    ///
+    /// The node table is loaded once when the filesystem is opened, so this
+    /// borrows from it instead of re-reading the image. Reading files only needs
+    /// `&self` as well, so files can be read while iterating, e.g. with
+    /// [`SquashFS::read_entry`], or see [`SquashFS::for_each_file`].
+    pub fn entries(&self) -> impl Iterator<Item = SquashFSEntry<'_>> + '_ {
+        let block_size = self.reader.block_size;
+        self.reader
+            .root
+            .nodes
+            .iter()
+            .zip(&self.inodes)
+            .map(move |(node, &inode)| SquashFSEntry::from_node(node, inode, block_size))
+    }
+
+    /// Returns an iterator over the entry at the given path and everything under it, in
+    /// path order. This is the recursive counterpart of [`SquashFS::read_dir`].
+    ///
+    /// Paths are compared component-wise, so `/usr/bin` covers `/usr/bin/foo` but
+    /// not `/usr/binaries`. Entries are not walked, the subtree is a range of the
+    /// path-sorted entries found with the path index.
+    ///
+    /// # Arguments
+    /// * `prefix` - The full path of the directory within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The entries under the prefix, including itself, or none if it doesn't exist.
+    pub fn entries_under<P: AsRef<Path>>(
+        &self,
+        prefix: P,
+    ) -> impl Iterator<Item = SquashFSEntry<'_>> + '_ {
+        let block_size = self.reader.block_size;
+        let range = match self.node_index(prefix.as_ref()) {
+            Some(idx) => idx..self.subtree_ends[idx],
+            None => 0..0,
+        };
+        self.reader.root.nodes[range.clone()]
+            .iter()
+            .zip(&self.inodes[range])
+            .map(move |(node, &inode)| SquashFSEntry::from_node(node, inode, block_size))
+    }
+
+    /// Calls the callback with every regular file in the SquashFS filesystem and its
+    /// contents, in path order. The contents are read into a buffer reused across
+    /// files, so only one file is held in memory at a time.
+    ///
+    /// # Arguments
+    /// * `f` - A function called with each file entry and its contents. Returning an
+    ///   error from it stops the iteration.
+    ///
+    /// # Returns
+    /// An empty result, or the first error reading a file or returned by the callback.
+    pub fn for_each_file<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&SquashFSEntry<'_>, &[u8]) -> Result<()>,
+    {
+        let mut contents = Vec::new();
+        for entry in self.entries() {
+            let EntryKind::File(file) = entry.kind else {
+                continue;
+            };
+            contents.clear();
+            self.file_reader(file)?.read_to_end(&mut contents)?;
+            f(&entry, &contents)?;
+        }
+        Ok(())
+    }
+
+    /// Collects all the entries in the SquashFS filesystem into a vector.
+    ///
+    /// Entries are plain data borrowing from the SquashFS instance, which is itself
+    /// `Send + Sync`, so the vector can be handed to other threads, e.g. with
+    /// [`std::thread::scope`], as long as the instance outlives them.
+    pub fn entries_vec(&self) -> Vec<SquashFSEntry<'_>> {
+        self.entries().collect()
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Returns a parallel iterator over all the entries in the SquashFS filesystem.
+    pub fn par_entries(&self) -> impl ParallelIterator<Item = SquashFSEntry<'_>> + '_ {
+        let block_size = self.reader.block_size;
+        self.reader
+            .root
+            .nodes
+            .par_iter()
+            .zip(&self.inodes)
+            .map(move |(node, &inode)| SquashFSEntry::from_node(node, inode, block_size))
+    }
+
+    /// Returns the number of entries in the SquashFS filesystem, including the root directory.
+    pub fn count_entries(&self) -> usize {
+        self.reader.root.nodes.len()
+    }
+
+    /// Counts the entries in the SquashFS filesystem by kind.
+    pub fn count_by_kind(&self) -> EntryCounts {
+        let mut counts = EntryCounts::default();
+        for node in &self.reader.root.nodes {
+            let counter = match &node.inner {
+                InnerNode::File(_) => &mut counts.files,
+                InnerNode::Dir(_) => &mut counts.directories,
+                InnerNode::Symlink(_) => &mut counts.symlinks,
+                InnerNode::BlockDevice(_) => &mut counts.block_devices,
+                InnerNode::CharacterDevice(_) => &mut counts.char_devices,
+                InnerNode::NamedPipe => &mut counts.fifos,
+                InnerNode::Socket => &mut counts.sockets,
+            };
+            *counter += 1;
+        }
+        counts
+    }
+
+    /// Returns the total uncompressed size in bytes of all regular files in the SquashFS filesystem.
+    pub fn total_size(&self) -> u64 {
+        self.reader
+            .root
+            .nodes
+            .iter()
+            .map(|node| match &node.inner {
+                InnerNode::File(file) => file_size(&file.basic, self.reader.block_size),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Returns the size in bytes the SquashFS image takes on disk, as recorded in the
+    /// superblock. Dividing [`SquashFS::total_size`] by it gives the compression ratio.
+    pub fn compressed_size(&self) -> u64 {
+        self.superblock.bytes_used
+    }
+
+    /// Walks the SquashFS filesystem in path order, calling the callback for every entry.
+    /// The callback decides whether to continue, skip the current directory's contents, or stop.
+    ///
+    /// # Arguments
+    /// * `f` - A function that takes an entry and returns the [`WalkAction`] to take next.
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&SquashFSEntry) -> WalkAction,
+    {
+        let mut skipped: Option<&Path> = None;
+        for (idx, node) in self.reader.root.nodes.iter().enumerate() {
+            if let Some(dir) = skipped {
+                if node.fullpath.starts_with(dir) {
+                    continue;
+                }
+                skipped = None;
+            }
+
+            let entry = self.entry_at(idx);
+            match f(&entry) {
+                WalkAction::Continue => {}
+                WalkAction::SkipDir => {
+                    if entry.is_dir() {
+                        skipped = Some(&node.fullpath);
+                    }
+                }
+                WalkAction::Stop => return,
+            }
+        }
+    }
+
+    /// Builds the directory hierarchy of the SquashFS filesystem as a tree rooted
+    /// at the root directory. Children of each directory are sorted by name.
+    ///
+    /// # Returns
+    /// The root directory with all entries nested under their parent directory.
+    pub fn tree(&self) -> DirNode<'_> {
+        let nodes = &self.reader.root.nodes;
+
+        // Group by parent index rather than relying on the order of the nodes, so a
+        // child is attached even if its directory comes later in the list.
+        let mut children = vec![Vec::new(); nodes.len()];
+        for (idx, node) in nodes.iter().enumerate() {
+            if let Some(&parent) = node.fullpath.parent().and_then(|p| self.index.get(p)) {
+                if parent != idx {
+                    children[parent].push(idx);
+                }
+            }
+        }
+        for indices in &mut children {
+            indices.sort_by(|&a, &b| {
+                nodes[a]
+                    .fullpath
+                    .file_name()
+                    .cmp(&nodes[b].fullpath.file_name())
+            });
+        }
+
+        let root = self.index.get(Path::new("/")).copied().unwrap_or(0);
+        self.dir_node(root, &children)
+    }
+
+    /// Builds the tree node for the node at `idx` from the child indices of every node.
+    fn dir_node(&self, idx: usize, children: &[Vec<usize>]) -> DirNode<'_> {
+        DirNode {
+            entry: self.entry_at(idx),
+            children: children[idx]
+                .iter()
+                .map(|&child| self.dir_node(child, children))
+                .collect(),
+        }
+    }
+
+    /// Looks up a single entry by its full path in the SquashFS filesystem.
+    ///
+    /// The lookup is backed by an index built when the filesystem is opened,
+    /// so it doesn't walk the entries. The leading `/` is optional, `usr/bin/foo` is the
+    /// same as `/usr/bin/foo`. The path may contain `.`, `..` and redundant
+    /// separators, `..` is resolved lexically rather than by following symlinks.
+    /// The same applies to every method taking the path of an entry.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the entry within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The entry at the given path, or None if it doesn't exist.
+    pub fn get_entry<P: AsRef<Path>>(&self, path: P) -> Option<SquashFSEntry<'_>> {
+        self.node_index(path.as_ref()).map(|idx| self.entry_at(idx))
+    }
+
+    /// Checks whether an entry exists at the given path in the SquashFS filesystem.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the entry within the SquashFS filesystem.
+    pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.node_index(path.as_ref()).is_some()
+    }
+
+    /// Returns the metadata of the entry at the given path, its size, kind, mode and
+    /// ownership, without reading any file data. Like [`SquashFS::get_entry`], this is
+    /// a lookup in the path index rather than a walk of the entries.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the entry within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The entry at the given path, or an error if it doesn't exist.
+    pub fn stat<P: AsRef<Path>>(&self, path: P) -> Result<SquashFSEntry<'_>> {
+        let path = path.as_ref();
+        self.get_entry(path)
+            .ok_or_else(|| SquishyError::FileNotFound(path.to_path_buf()))
+    }
+
+    /// Returns the directories containing the entry at the given path, from the root down.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the entry within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The ancestor directory entries, empty for the root itself, or an error if the
+    /// path doesn't exist.
+    pub fn ancestors<P: AsRef<Path>>(&self, path: P) -> Result<Vec<SquashFSEntry<'_>>> {
+        let path = path.as_ref();
+        let idx = self
+            .node_index(path)
+            .ok_or_else(|| SquishyError::FileNotFound(path.to_path_buf()))?;
+        let mut ancestors: Vec<_> = self.reader.root.nodes[idx]
+            .fullpath
+            .ancestors()
+            .skip(1)
+            .filter_map(|ancestor| self.get_entry(ancestor))
+            .collect();
+        ancestors.reverse();
+        Ok(ancestors)
+    }
+
+    /// Lists the immediate children of a directory in the SquashFS filesystem.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the directory within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The entries directly inside the directory, or an error if the path doesn't exist
+    /// or isn't a directory.
+    pub fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<Vec<SquashFSEntry<'_>>> {
+        Ok(self.read_dir_iter(path)?.collect())
+    }
+
+    /// Lazily lists the immediate children of a directory in the SquashFS filesystem,
+    /// e.g. to expand directories on demand when browsing a large image.
+    ///
+    /// Only the children themselves are visited, whatever is under them is skipped over,
+    /// so listing the root of a huge image doesn't go through every entry.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the directory within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// An iterator over the entries directly inside the directory, in path order, or an
+    /// error if the path doesn't exist or isn't a directory.
+    pub fn read_dir_iter<P: AsRef<Path>>(&self, path: P) -> Result<ReadDir<'_>> {
+        let path = path.as_ref();
+        let idx = self
+            .node_index(path)
+            .ok_or_else(|| SquishyError::FileNotFound(path.to_path_buf()))?;
+        if !matches!(self.reader.root.nodes[idx].inner, InnerNode::Dir(_)) {
+            return Err(SquishyError::NotADirectory(path.to_path_buf()));
+        }
+        Ok(ReadDir {
+            nodes: &self.reader.root.nodes,
+            inodes: &self.inodes,
+            subtree_ends: &self.subtree_ends,
+            next: idx + 1,
+            end: self.subtree_ends[idx],
+            block_size: self.reader.block_size,
+        })
+    }
+
+    /// Lists the entries at the root of the SquashFS filesystem, e.g. `AppRun`, the
+    /// desktop file, `.DirIcon` and `usr` for an AppImage. Same as `read_dir("/")`.
+    ///
+    /// # Returns
+    /// The entries directly inside the root directory, or an error if the image has no root directory.
+    pub fn root_entries(&self) -> Result<Vec<SquashFSEntry<'_>>> {
+        self.read_dir("/")
+    }
+
+    /// Returns the index range of the nodes of the directory at the given path, starting
+    /// with the directory itself and followed by everything under it.
+    fn subtree<P: AsRef<Path>>(&self, path: P) -> Result<Range<usize>> {
+        let path = path.as_ref();
+        let idx = self
+            .node_index(path)
+            .ok_or_else(|| SquishyError::FileNotFound(path.to_path_buf()))?;
+        if !matches!(self.reader.root.nodes[idx].inner, InnerNode::Dir(_)) {
+            return Err(SquishyError::NotADirectory(path.to_path_buf()));
+        }
+
+        Ok(idx..self.subtree_ends[idx])
+    }
+
+    /// Returns the entry for the node at the given index.
+    fn entry_at(&self, idx: usize) -> SquashFSEntry<'_> {
+        SquashFSEntry::from_node(
+            &self.reader.root.nodes[idx],
+            self.inodes[idx],
+            self.reader.block_size,
+        )
+    }
+
+    /// Looks up the node at the given path using the path index.
+    fn node<P: AsRef<Path>>(&self, path: P) -> Option<&Node<SquashfsFileReader>> {
+        self.node_index(path.as_ref())
+            .map(|idx| &self.reader.root.nodes[idx])
+    }
+
+    /// Looks up the index of the node at the given path. Redundant separators and `.`
+    /// already compare equal as paths, `..` is resolved lexically without following symlinks.
+    /// Relative paths are taken from the root.
+    fn node_index(&self, path: &Path) -> Option<usize> {
+        match self.index.get(path) {
+            Some(&idx) => Some(idx),
+            None => self
+                .index
+                .get(&normalize_path(&Path::new("/").join(path)))
+                .copied(),
+        }
+    }
+
+    /// Looks up an entry by its full path, returning a handle that can be passed to
+    /// [`SquashFS::read_file`] and similar methods instead of the path, so the entry
+    /// isn't looked up again.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the entry within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// A handle to the entry, if found
+    pub fn resolve_node<P: AsRef<Path>>(&self, path: P) -> Option<NodeHandle<'_>> {
+        self.node_index(path.as_ref())
+            .map(|index| self.handle(index))
+    }
+
+    /// Returns a handle to the node at the given index.
+    fn handle(&self, index: usize) -> NodeHandle<'_> {
+        NodeHandle {
+            nodes: &self.reader.root.nodes,
+            inodes: &self.inodes,
+            index,
+            block_size: self.reader.block_size,
+        }
+    }
+
+    /// Returns the first entry, in path order, whose path matches the predicate.
+    /// Scanning stops at the first match.
+    ///
+    /// # Arguments
+    /// * `predicate` - A function that takes a &Path and returns a bool, indicating whether the entry matches.
+    pub fn find_first<F>(&self, mut predicate: F) -> Option<SquashFSEntry<'_>>
+    where
+        F: FnMut(&Path) -> bool,
+    {
+        self.reader
+            .root
+            .nodes
+            .iter()
+            .position(|node| predicate(&node.fullpath))
+            .map(|idx| self.entry_at(idx))
+    }
+
+    /// Looks up a single entry by its full path, ignoring ASCII case.
+    ///
+    /// Only ASCII letters are compared case-insensitively, other characters must match
+    /// exactly. A leading `/` is optional. Unlike [`SquashFS::get_entry`], this walks the entries.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the entry within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The first entry, in path order, matching the given path, or None if there is none.
+    pub fn get_entry_ci<P: AsRef<Path>>(&self, path: P) -> Option<SquashFSEntry<'_>> {
+        self.find_entries_ci(path).next()
+    }
+
+    /// Returns an iterator over all the entries whose full path matches the given
+    /// path ignoring ASCII case, e.g. both `/usr/bin/foo` and `/usr/bin/FOO` for `USR/BIN/FOO`.
+    ///
+    /// # Arguments
+    /// * `path` - The full path to match within the SquashFS filesystem.
+    pub fn find_entries_ci<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> impl Iterator<Item = SquashFSEntry<'_>> + '_ {
+        let path = path.as_ref().to_path_buf();
+        self.find_entries(move |entry_path| eq_ignore_ascii_case(entry_path, &path))
+    }
+
+    /// Returns an iterator over all the entries in the SquashFS filesystem
+    /// that match the provided predicate function.
+    ///
+    /// # Arguments
+    /// * `predicate` - A function that takes a &Path and returns a bool, indicating whether the entry should be included.
+    pub fn find_entries<'s, F>(
+        &'s self,
+        mut predicate: F,
+    ) -> impl Iterator<Item = SquashFSEntry<'s>> + 's
+    where
+        F: FnMut(&Path) -> bool + 's,
+    {
+        self.entries().filter(move |entry| predicate(&entry.path))
+    }
+
+    /// Collects the entries whose path matches the predicate, transformed by `map`.
+    ///
+    /// This is a convenience over `find_entries(predicate).map(map).collect()`, with
+    /// entries only built for the paths that match.
+    ///
+    /// # Arguments
+    /// * `predicate` - A function that takes a &Path and returns a bool, indicating whether the entry should be included.
+    /// * `map` - A function that turns a matching entry into the collected value.
+    pub fn collect_entries<T, F, M>(&self, mut predicate: F, map: M) -> Vec<T>
+    where
+        F: FnMut(&Path) -> bool,
+        M: FnMut(SquashFSEntry<'_>) -> T,
+    {
+        self.reader
+            .root
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| predicate(&node.fullpath))
+            .map(|(idx, _)| self.entry_at(idx))
+            .map(map)
+            .collect()
+    }
+
+    /// Returns an iterator over all the entries in the SquashFS filesystem of the given kind.
+    ///
+    /// # Arguments
+    /// * `kind` - The kind of entries to include, e.g. [`EntryKindFilter::AnySymlink`].
+    pub fn entries_of_kind(
+        &self,
+        kind: EntryKindFilter,
+    ) -> impl Iterator<Item = SquashFSEntry<'_>> + '_ {
+        self.entries()
+            .filter(move |entry| kind.matches(&entry.kind))
+    }
+
+    /// Returns the regular files in the SquashFS filesystem sorted by their size, e.g.
+    /// to report the biggest files of an image. Files of the same size stay in path order.
+    ///
+    /// # Arguments
+    /// * `descending` - Whether to put the biggest files first instead of the smallest.
+    pub fn entries_by_size(&self, descending: bool) -> Vec<SquashFSEntry<'_>> {
+        let mut files: Vec<_> = self.entries_of_kind(EntryKindFilter::File).collect();
+        if descending {
+            files.sort_by_key(|entry| std::cmp::Reverse(entry.size));
         } else {
-            Ok(None)
+            files.sort_by_key(|entry| entry.size);
+        }
+        files
+    }
+
+    /// Returns an iterator over all the entries in the SquashFS filesystem
+    /// whose path matches the provided glob pattern, e.g. `**/*.desktop`.
+    ///
+    /// Patterns are matched against paths without the leading `/`, unless the
+    /// pattern itself starts with `/`. `*` and `?` stay within a single path
+    /// component, use `**` to match across directories, e.g. `usr/share/icons/*` only
+    /// matches the direct children of `icons` while `usr/share/icons/**` matches
+    /// everything under it.
+    ///
+    /// # Arguments
+    /// * `pattern` - The glob pattern to match entry paths against.
+    #[cfg(feature = "glob")]
+    pub fn find_glob(&self, pattern: &str) -> Result<impl Iterator<Item = SquashFSEntry<'_>> + '_> {
+        let anchored = pattern.starts_with('/');
+        let matcher = globset::GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()?
+            .compile_matcher();
+        Ok(self.entries().filter(move |entry| {
+            if anchored {
+                matcher.is_match(&entry.path)
+            } else {
+                matcher.is_match(entry.path.strip_prefix("/").unwrap_or(&entry.path))
+            }
+        }))
+    }
+
+    /// Reads the contents of the specified file from the SquashFS filesystem.
+    ///
+    /// Data is read through backhand's file reader, which handles both full data blocks
+    /// and the fragment blocks that small files and the tails of larger files are packed into.
+    /// Sparse blocks, which aren't stored in the image, are read back as zeros.
+    ///
+    /// # Arguments
+    /// * `entry` - The path to the file within the SquashFS filesystem, or a handle to it.
+    ///
+    /// # Returns
+    /// The contents of the file as a Vec<u8>, [`SquishyError::FileNotFound`] if nothing
+    /// exists at the path, [`SquishyError::NotAFile`] if it isn't a regular file, or
+    /// [`SquishyError::FileTooLarge`] if it is over 4 GiB. The same goes for every
+    /// method reading file contents.
+    pub fn read_file<L: EntryLocator>(&self, entry: L) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.read_file_into(entry, &mut contents)?;
+        Ok(contents)
+    }
+
+    /// Reads the contents of the file with the given inode number, e.g. one found by
+    /// looking for hardlinks with [`SquashFSEntry::inode`], without going through its path.
+    ///
+    /// # Arguments
+    /// * `inode` - The inode number of the file.
+    ///
+    /// # Returns
+    /// The contents of the file, [`SquishyError::InodeNotFound`] if no entry has the
+    /// inode number, or [`SquishyError::NotAFile`] if it isn't a regular file.
+    pub fn read_file_by_inode(&self, inode: u32) -> Result<Vec<u8>> {
+        let idx = self
+            .inode_index
+            .get(&inode)
+            .ok_or(SquishyError::InodeNotFound(inode))?;
+        self.read_file(self.handle(*idx))
+    }
+
+    /// Reads the contents of the specified file from the SquashFS filesystem as text,
+    /// e.g. for scripts or desktop entries.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The contents of the file, or an error if the file is not found or is not valid UTF-8.
+    pub fn read_file_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let path = path.as_ref();
+        String::from_utf8(self.read_file(path)?)
+            .map_err(|_| SquishyError::InvalidUtf8(path.to_path_buf()))
+    }
+
+    /// Reads the contents of several files from the SquashFS filesystem. With the `rayon`
+    /// feature, the files are decompressed in parallel on the rayon thread pool.
+    ///
+    /// # Arguments
+    /// * `paths` - The paths to the files within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The contents of each file, or the error reading it, in the order of `paths`.
+    pub fn read_files<P: AsRef<Path> + Sync>(&self, paths: &[P]) -> Vec<Result<Vec<u8>>> {
+        #[cfg(feature = "rayon")]
+        let paths = paths.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let paths = paths.iter();
+        paths.map(|path| self.read_file(path)).collect()
+    }
+
+    /// Reads the contents of the specified file from the SquashFS filesystem into the
+    /// provided buffer, so one buffer can be reused across many files.
+    ///
+    /// The buffer is cleared before it is filled, its capacity is kept.
+    ///
+    /// # Arguments
+    /// * `entry` - The path to the file within the SquashFS filesystem, or a handle to it.
+    /// * `buf` - The buffer to read the file contents into.
+    ///
+    /// # Returns
+    /// The number of bytes read, or an error as with [`SquashFS::read_file`]. The buffer
+    /// holds whatever was read before the error.
+    pub fn read_file_into<L: EntryLocator>(&self, entry: L, buf: &mut Vec<u8>) -> Result<usize> {
+        buf.clear();
+        let file = self.basic_file(entry)?;
+        buf.reserve(file_size(file, self.reader.block_size) as usize);
+        Ok(self.file_reader(file)?.read_to_end(buf)?)
+    }
+
+    /// Reads the contents of a file entry, e.g. one yielded by [`SquashFS::entries`],
+    /// without looking its path up again.
+    ///
+    /// # Arguments
+    /// * `entry` - The file entry within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The contents of the file, or an error if the entry is not a file or cannot be read.
+    pub fn read_entry(&self, entry: &SquashFSEntry) -> Result<Vec<u8>> {
+        match entry.kind {
+            EntryKind::File(file) => {
+                let mut contents = Vec::with_capacity(entry.size as usize);
+                self.file_reader(file)?.read_to_end(&mut contents)?;
+                Ok(contents)
+            }
+            _ => Err(SquishyError::NotAFile(entry.path.clone())),
+        }
+    }
+
+    /// Reads a window of the specified file from the SquashFS filesystem.
+    /// The file is opened with [`SquashFS::open_file_seek`] and seeked to `offset`, so
+    /// only the blocks covering the window are decompressed.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file within the SquashFS filesystem.
+    /// * `offset` - Offset within the file to start reading from.
+    /// * `len` - Maximum number of bytes to read.
+    ///
+    /// # Returns
+    /// The bytes in the window, which is shorter than `len` if it extends past the
+    /// end of the file, or an error if the file is not found.
+    pub fn read_file_range<P: AsRef<Path>>(
+        &self,
+        path: P,
+        offset: u64,
+        len: usize,
+    ) -> Result<Vec<u8>> {
+        let mut file = self.open_file_seek(path)?;
+        let len = file.size.saturating_sub(offset).min(len as u64);
+
+        file.seek(SeekFrom::Start(offset))?;
+        let mut contents = Vec::with_capacity(len as usize);
+        file.take(len).read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    /// Opens the specified file from the SquashFS filesystem for streaming reads.
+    /// The contents are decompressed lazily as the returned reader is consumed, and
+    /// sparse blocks are read as zeros, the same bytes as [`SquashFS::read_file`].
+    ///
+    /// # Arguments
+    /// * `entry` - The path to the file within the SquashFS filesystem, or a handle to it.
+    ///
+    /// # Returns
+    /// A reader over the file contents, or an error if the path isn't a regular file.
+    pub fn open_file<L: EntryLocator>(&self, entry: L) -> Result<impl Read + '_> {
+        let file = self.basic_file(entry)?;
+        self.file_reader(file)
+    }
+
+    /// Opens the specified file from the SquashFS filesystem for random access, e.g. to
+    /// parse an ELF or ZIP file stored in the image without extracting it first.
+    ///
+    /// Blocks are decompressed lazily as they are read. Seeking within the current block
+    /// is free, seeking anywhere else, backwards included, re-decompresses the block
+    /// containing the new position. Sparse blocks are read as zeros.
+    ///
+    /// # Arguments
+    /// * `entry` - The path to the file within the SquashFS filesystem, or a handle to it.
+    ///
+    /// # Returns
+    /// A reader that can seek within the decompressed file, or an error if the file is not found.
+    pub fn open_file_seek<L: EntryLocator>(&self, entry: L) -> Result<SeekableFile<'_, 'a>> {
+        let file = self.basic_file(entry)?;
+        self.seekable_file(file)
+    }
+
+    /// Returns a reader that can seek within the decompressed contents of a basic file,
+    /// or an error if the file is too large to be read.
+    fn seekable_file<'s>(&'s self, file: &'s BasicFile) -> Result<SeekableFile<'s, 'a>> {
+        let size = check_file_size(file, self.reader.block_size)?;
+        let block_starts = file
+            .block_sizes
+            .iter()
+            .scan(u64::from(file.blocks_start), |start, block| {
+                let block_start = *start;
+                *start += u64::from(block.size());
+                Some(block_start)
+            })
+            .collect();
+        Ok(SeekableFile {
+            squashfs: self,
+            file,
+            size,
+            position: 0,
+            block_starts,
+            block_index: None,
+            block: Vec::new(),
+        })
+    }
+
+    /// Reads the specified file from the SquashFS filesystem as an iterator of chunks.
+    /// The contents are decompressed lazily as the iterator is consumed, every chunk
+    /// holds exactly `chunk_size` bytes except for the last one, which may be shorter.
+    /// The iterator ends after the first error.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file within the SquashFS filesystem.
+    /// * `chunk_size` - The number of bytes per chunk, a size of 0 is taken as 1.
+    ///
+    /// # Returns
+    /// An iterator over the file contents, or an error if the path isn't a regular file.
+    pub fn read_file_chunks<'s, P: AsRef<Path>>(
+        &'s self,
+        path: P,
+        chunk_size: usize,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>> + 's> {
+        let chunk_size = chunk_size.max(1);
+        let mut reader = Some(self.file_reader(self.basic_file(path)?)?);
+        Ok(std::iter::from_fn(move || {
+            let mut chunk = Vec::with_capacity(chunk_size);
+            match (reader.as_mut()?)
+                .take(chunk_size as u64)
+                .read_to_end(&mut chunk)
+            {
+                Ok(0) => {
+                    reader = None;
+                    None
+                }
+                Ok(_) => Some(Ok(chunk)),
+                Err(e) => {
+                    reader = None;
+                    Some(Err(e.into()))
+                }
+            }
+        }))
+    }
+
+    /// Computes the SHA-256 digest of the specified file from the SquashFS filesystem.
+    /// The contents are hashed as they are decompressed, so memory use stays bounded
+    /// regardless of the file size.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The digest of the decompressed file contents, or an error if the file is not found
+    /// or cannot be read.
+    #[cfg(feature = "hash")]
+    pub fn hash_file<P: AsRef<Path>>(&self, path: P) -> Result<[u8; 32]> {
+        let mut hasher = Sha256::new();
+        for chunk in self.read_file_chunks(path, 64 * 1024)? {
+            hasher.update(chunk?);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Returns a reader over the decompressed contents of a basic file, which reads
+    /// sparse blocks as zeros, or an error if the file is too large to be read.
+    ///
+    /// backhand reads a sparse block as empty, ending the file early, so files with
+    /// sparse blocks are read a block at a time instead.
+    fn file_reader<'s>(&'s self, file: &'s BasicFile) -> Result<Box<dyn Read + 's>> {
+        if file.block_sizes.iter().any(|block| block.size() == 0) {
+            return Ok(Box::new(self.seekable_file(file)?));
+        }
+        check_file_size(file, self.reader.block_size)?;
+        Ok(Box::new(self.reader.file(file).reader()))
+    }
+
+    /// Checks that every regular file in the SquashFS filesystem decompresses without
+    /// errors and to its recorded size. Files are streamed and their contents discarded,
+    /// so memory use stays bounded regardless of the image size.
+    ///
+    /// # Returns
+    /// A report of the files read and the ones that failed, which doesn't stop the check.
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        for node in &self.reader.root.nodes {
+            let InnerNode::File(file) = &node.inner else {
+                continue;
+            };
+            report.files += 1;
+
+            let expected = file_size(&file.basic, self.reader.block_size);
+            let read = self
+                .file_reader(&file.basic)
+                .and_then(|mut reader| Ok(std::io::copy(&mut reader, &mut std::io::sink())?));
+            match read {
+                Ok(read) => {
+                    report.bytes += read;
+                    if read != expected {
+                        let error = std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            format!("read {read} bytes, expected {expected}"),
+                        );
+                        report.errors.push((node.fullpath.clone(), error.into()));
+                    }
+                }
+                Err(e) => report.errors.push((node.fullpath.clone(), e)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Describes how the specified file is stored in the image: its data blocks, their
+    /// stored sizes and its tail fragment. This is an advanced API for analyzing
+    /// storage efficiency, the file contents are not read.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The layout of the file, or an error if the path isn't a regular file.
+    pub fn file_layout<P: AsRef<Path>>(&self, path: P) -> Result<FileLayout> {
+        let file = self.basic_file(path)?;
+        let block_size = self.reader.block_size;
+        let size = file_size(file, block_size);
+        let blocks = &file.block_sizes;
+        // Empty files may still name a fragment, without anything stored in it.
+        let tail = size.saturating_sub(blocks.len() as u64 * u64::from(block_size));
+        let fragment_index =
+            (file.frag_index != 0xffffffff && tail != 0).then_some(file.frag_index);
+
+        Ok(FileLayout {
+            size,
+            block_size,
+            blocks: blocks.len(),
+            sparse_blocks: blocks.iter().filter(|block| block.size() == 0).count(),
+            uncompressed_blocks: blocks.iter().filter(|block| block.uncompressed()).count(),
+            stored_size: blocks.iter().map(|block| u64::from(block.size())).sum(),
+            fragment_index,
+            fragment_size: if fragment_index.is_some() { tail } else { 0 },
+        })
+    }
+
+    /// Looks up the regular file at the given path.
+    fn basic_file<L: EntryLocator>(&self, entry: L) -> Result<&BasicFile> {
+        let node = entry
+            .node_index(self)
+            .map(|idx| &self.reader.root.nodes[idx]);
+        match node.map(|node| &node.inner) {
+            Some(InnerNode::File(file)) => Ok(&file.basic),
+            Some(_) => Err(SquishyError::NotAFile(entry.entry_path().to_path_buf())),
+            None => Err(SquishyError::FileNotFound(entry.entry_path().to_path_buf())),
+        }
+    }
+
+    /// Streams the contents of the specified file from the SquashFS filesystem
+    /// into the provided writer, without buffering the whole file in memory.
+    ///
+    /// # Arguments
+    /// * `entry` - The path to the file within the SquashFS filesystem, or a handle to it.
+    /// * `writer` - The writer to copy the decompressed file contents into.
+    ///
+    /// # Returns
+    /// The number of bytes written, or an error if the file is not found or cannot be read.
+    pub fn read_file_to_writer<L: EntryLocator, W: Write>(
+        &self,
+        entry: L,
+        writer: &mut W,
+    ) -> Result<u64> {
+        let file = self.basic_file(entry)?;
+        self.copy_file(file, writer)
+    }
+
+    /// Copies the decompressed contents of a basic file into the writer.
+    fn copy_file<W: Write>(&self, file: &BasicFile, writer: &mut W) -> Result<u64> {
+        let mut reader = self.file_reader(file)?;
+        let copied = match self.copy_buffer_size {
+            Some(size) => std::io::copy(&mut BufReader::with_capacity(size, reader), writer),
+            None => std::io::copy(&mut reader, writer),
+        };
+        Ok(copied?)
+    }
+
+    /// Writes the contents of the specified file from the SquashFS filesystem
+    /// to the specified destination path.
+    ///
+    /// The file is created with default permissions, use [`SquashFS::write_entry`]
+    /// to keep the mode recorded in the image.
+    ///
+    /// # Arguments
+    /// * `file` - The basic file within the SquashFS filesystem.
+    /// * `dest` - The destination path to write the file to.
+    ///
+    /// # Returns
+    /// An empty result, or an error if the file cannot be read or written.
+    pub fn write_file<P: AsRef<Path>>(&self, file: &BasicFile, dest: P) -> Result<()> {
+        let output_file = File::create(dest)?;
+        let mut writer = BufWriter::new(output_file);
+        self.copy_file(file, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the contents of a file entry from the SquashFS filesystem
+    /// to the specified destination path, keeping the entry's mode.
+    ///
+    /// # Arguments
+    /// * `entry` - The file entry within the SquashFS filesystem.
+    /// * `dest` - The destination path to write the file to.
+    ///
+    /// # Returns
+    /// An empty result, or an error if the entry is not a file or cannot be read or written.
+    pub fn write_entry<P: AsRef<Path>>(&self, entry: &SquashFSEntry, dest: P) -> Result<()> {
+        match entry.kind {
+            EntryKind::File(file) => self.write_file_with_permissions(file, dest, entry.header),
+            _ => Err(SquishyError::NotAFile(entry.path.clone())),
+        }
+    }
+
+    /// Writes the contents of the specified file from the SquashFS filesystem
+    /// to the specified destination path with permissions.
+    ///
+    /// # Arguments
+    /// * `file` - The basic file within the SquashFS filesystem.
+    /// * `dest` - The destination path to write the file to.
+    /// * `header` - Node header containing file information.
+    ///
+    /// # Returns
+    /// An empty result, or an error if the file cannot be read or written.
+    pub fn write_file_with_permissions<P: AsRef<Path>>(
+        &self,
+        file: &BasicFile,
+        dest: P,
+        header: NodeHeader,
+    ) -> Result<()> {
+        let output_file = File::create(&dest)?;
+        let mode = u32::from(header.permissions);
+        fs::set_permissions(dest, Permissions::from_mode(mode))?;
+        let mut writer = BufWriter::new(output_file);
+        self.copy_file(file, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the contents of the specified file from the SquashFS filesystem
+    /// to the specified destination path, and sets its modification time.
+    ///
+    /// # Arguments
+    /// * `file` - The basic file within the SquashFS filesystem.
+    /// * `dest` - The destination path to write the file to.
+    /// * `mtime` - Modification time in seconds since the Unix epoch, as stored in the
+    ///   inode (see [`SquashFSEntry::mtime`]).
+    ///
+    /// # Returns
+    /// An empty result, or an error if the file cannot be read or written.
+    pub fn write_file_with_mtime<P: AsRef<Path>>(
+        &self,
+        file: &BasicFile,
+        dest: P,
+        mtime: u32,
+    ) -> Result<()> {
+        let output_file = File::create(dest)?;
+        let mut writer = BufWriter::new(output_file);
+        self.copy_file(file, &mut writer)?;
+        let output_file = writer.into_inner().map_err(|e| e.into_error())?;
+        output_file.set_modified(UNIX_EPOCH + Duration::from_secs(u64::from(mtime)))?;
+        Ok(())
+    }
+
+    /// Reads the target stored in a symlink, without resolving it.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the symlink within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The raw target of the symlink, None if the entry is not a symlink,
+    /// or an error if the path doesn't exist.
+    pub fn get_symlink_target<P: AsRef<Path>>(&self, path: P) -> Result<Option<PathBuf>> {
+        let path = path.as_ref();
+        match self.node(path) {
+            Some(node) => match &node.inner {
+                InnerNode::Symlink(symlink) => Ok(Some(symlink.link.clone())),
+                _ => Ok(None),
+            },
+            None => Err(SquishyError::FileNotFound(path.to_path_buf())),
+        }
+    }
+
+    /// Resolves the symlink chain starting from the specified entry,
+    /// returning the final target entry or an error if a cycle is detected.
+    ///
+    /// At most [`MAX_SYMLINK_HOPS`] symlinks are followed, matching Linux's `MAXSYMLINKS`.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to resolve the symlink for.
+    ///
+    /// # Returns
+    /// The final target entry, or None if the entry is not a symlink, the chain is
+    /// dangling or it points outside the image, or an error if a cycle is detected or
+    /// the chain is too long. Use [`SquashFS::check_symlink`] to tell the None cases apart.
+    pub fn resolve_symlink(&self, entry: &SquashFSEntry) -> Result<Option<SquashFSEntry<'_>>> {
+        self.resolve_symlink_with_limit(entry, MAX_SYMLINK_HOPS)
+    }
+
+    /// Resolves the symlink chain starting from the specified entry,
+    /// following at most `max_hops` symlinks.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to resolve the symlink for.
+    /// * `max_hops` - The maximum number of symlinks to follow.
+    ///
+    /// # Returns
+    /// The final target entry, or an error if a cycle is detected or the chain is
+    /// longer than `max_hops`. None is returned when:
+    /// * the entry is not a symlink,
+    /// * the chain is dangling, a symlink in it points to a path missing from the image,
+    /// * the chain points outside the image, to an absolute path missing from it or
+    ///   above the root.
+    ///
+    /// Use [`SquashFS::check_symlink`] to tell these cases apart.
+    pub fn resolve_symlink_with_limit(
+        &self,
+        entry: &SquashFSEntry,
+        max_hops: usize,
+    ) -> Result<Option<SquashFSEntry<'_>>> {
+        match &entry.kind {
+            EntryKind::Symlink(_) => {
+                let mut visited = HashSet::new();
+                match self.follow_symlink(&entry.path, &mut visited, max_hops)? {
+                    SymlinkResolution::Resolved(target) => Ok(Some(target)),
+                    _ => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Follows a single symlink hop from the specified entry, returning the entry the
+    /// symlink points to, which may itself be a symlink.
+    ///
+    /// # Arguments
+    /// * `entry` - The symlink entry to follow.
+    ///
+    /// # Returns
+    /// The entry the symlink points to, or None if the entry is not a symlink or its target doesn't exist.
+    pub fn resolve_symlink_once(&self, entry: &SquashFSEntry) -> Result<Option<SquashFSEntry<'_>>> {
+        match &entry.kind {
+            EntryKind::Symlink(link) => Ok(self.get_entry(resolve_link_target(&entry.path, link))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolves the symlink chain starting from the specified entry, telling apart
+    /// symlinks that resolve, dangling symlinks, symlinks pointing outside the image
+    /// and entries that aren't symlinks.
+    ///
+    /// At most [`MAX_SYMLINK_HOPS`] symlinks are followed.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to resolve the symlink for.
+    ///
+    /// # Returns
+    /// How the entry resolves, or an error if a cycle is detected.
+    pub fn check_symlink(&self, entry: &SquashFSEntry) -> Result<SymlinkResolution<'_>> {
+        match &entry.kind {
+            EntryKind::Symlink(_) => {
+                let mut visited = HashSet::new();
+                self.follow_symlink(&entry.path, &mut visited, MAX_SYMLINK_HOPS)
+            }
+            _ => Ok(SymlinkResolution::NotSymlink),
+        }
+    }
+
+    /// Reads the contents of the file at the given path, following it first if it is a
+    /// symlink, e.g. an `AppRun` symlink to the binary in `usr/bin`. Relative targets are
+    /// resolved from the symlink's directory, and at most [`MAX_SYMLINK_HOPS`] symlinks
+    /// are followed.
+    ///
+    /// The path is resolved with [`SquashFS::canonicalize`], so symlinks are followed in
+    /// every component, e.g. `/lib/app` works when `/lib` is a symlink to `usr/lib`.
+    ///
+    /// # Arguments
+    /// * `path` - The full path of the file or symlink within the SquashFS filesystem.
+    ///
+    /// # Returns
+    /// The contents of the final target file, [`SquishyError::NotAFile`] with the path
+    /// the chain ends at if it isn't a file, e.g. a directory, or an error if the path
+    /// doesn't exist, or the chain is dangling, points outside the image or has a cycle.
+    pub fn read_through_symlink<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+        let Some(resolved) = self.canonicalize(path)? else {
+            return Err(self.unresolved_path_error(path));
+        };
+        let entry = self.stat(&resolved)?;
+        match entry.kind {
+            EntryKind::File(_) => self.read_entry(&entry),
+            _ => Err(SquishyError::NotAFile(resolved)),
+        }
+    }
+
+    /// Returns the error for a path [`SquashFS::canonicalize`] couldn't resolve: where
+    /// its last symlink points if that is what is missing, or that the path is not found.
+    fn unresolved_path_error(&self, path: &Path) -> SquishyError {
+        let link = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => self
+                .canonicalize(parent)
+                .ok()
+                .flatten()
+                .and_then(|parent| self.get_entry(parent.join(name))),
+            _ => None,
+        };
+        if let Some(link) = link {
+            if let Ok(SymlinkResolution::Dangling(target) | SymlinkResolution::External(target)) =
+                self.check_symlink(&link)
+            {
+                return SquishyError::SymlinkError(format!(
+                    "Symlink {} points to {}, which is not in the image",
+                    link.path.display(),
+                    target.display()
+                ));
+            }
+        }
+        SquishyError::FileNotFound(path.to_path_buf())
+    }
+
+    /// Follows the symlink chain starting from the specified entry, returning every hop.
+    ///
+    /// At most [`MAX_SYMLINK_HOPS`] symlinks are followed.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to start from.
+    ///
+    /// # Returns
+    /// The entry itself followed by each entry the chain goes through, ending at the
+    /// final target, or at the last symlink if its target doesn't exist in the image.
+    /// An error is returned if a cycle is detected or the hop limit is exceeded.
+    pub fn symlink_chain(&self, entry: &SquashFSEntry) -> Result<Vec<SquashFSEntry<'_>>> {
+        let Some(mut current) = self.get_entry(&entry.path) else {
+            return Err(SquishyError::FileNotFound(entry.path.clone()));
+        };
+        let mut visited = HashSet::from([current.path.clone()]);
+        let mut chain = Vec::new();
+
+        while let EntryKind::Symlink(link) = &current.kind {
+            if chain.len() == MAX_SYMLINK_HOPS {
+                return Err(SquishyError::SymlinkError(
+                    "Too many levels of symbolic links".into(),
+                ));
+            }
+            let target = resolve_link_target(&current.path, link);
+            if !visited.insert(target.clone()) {
+                return Err(SquishyError::SymlinkError("Cyclic symlink detected".into()));
+            }
+            let Some(next) = self.get_entry(&target) else {
+                break;
+            };
+            chain.push(std::mem::replace(&mut current, next));
+        }
+
+        chain.push(current);
+        Ok(chain)
+    }
+
+    /// Resolves the given path to its canonical form, following symlinks in every
+    /// component, not just the last one.
+    ///
+    /// # Arguments
+    /// * `path` - The path to canonicalize, relative paths are taken from the root.
+    ///
+    /// # Returns
+    /// The fully resolved path, None if any component doesn't exist, or an error if
+    /// more than [`MAX_SYMLINK_HOPS`] symlinks are followed, e.g. because of a cycle.
+    pub fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<Option<PathBuf>> {
+        let mut resolved = PathBuf::from("/");
+        let mut pending: Vec<Component> = path.as_ref().components().rev().collect();
+        let mut hops = 0;
+
+        while let Some(component) = pending.pop() {
+            match component {
+                Component::RootDir => resolved = PathBuf::from("/"),
+                Component::CurDir | Component::Prefix(_) => {}
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::Normal(name) => {
+                    let candidate = resolved.join(name);
+                    let Some(node) = self.node(&candidate) else {
+                        return Ok(None);
+                    };
+                    match &node.inner {
+                        InnerNode::Symlink(symlink) => {
+                            hops += 1;
+                            if hops > MAX_SYMLINK_HOPS {
+                                return Err(SquishyError::SymlinkError(
+                                    "Too many levels of symbolic links".into(),
+                                ));
+                            }
+                            pending.extend(symlink.link.components().rev());
+                        }
+                        _ => resolved = candidate,
+                    }
+                }
+            }
+        }
+
+        Ok(Some(resolved))
+    }
+
+    /// Recursively follows symlinks, keeping track of the visited paths
+    /// to detect and report cycles. Relative symlink targets are resolved
+    /// against the directory containing the symlink.
+    ///
+    /// # Arguments
+    /// * `path` - The path to follow.
+    /// * `visited` - A mutable HashSet to keep track of visited paths.
+    /// * `hops_left` - The number of symlinks that may still be followed.
+    ///
+    /// # Returns
+    /// The final target entry, or the first missing path if the chain is dangling,
+    /// or an error if a cycle is detected or the hop limit is exceeded.
+    fn follow_symlink(
+        &self,
+        path: &Path,
+        visited: &mut HashSet<PathBuf>,
+        hops_left: usize,
+    ) -> Result<SymlinkResolution<'_>> {
+        if !visited.insert(path.to_path_buf()) {
+            return Err(SquishyError::SymlinkError("Cyclic symlink detected".into()));
+        }
+
+        match self.node_index(path) {
+            Some(idx) => match &self.reader.root.nodes[idx].inner {
+                InnerNode::Symlink(symlink) => {
+                    if hops_left == 0 {
+                        return Err(SquishyError::SymlinkError(
+                            "Too many levels of symbolic links".into(),
+                        ));
+                    }
+                    let target = resolve_link_target(path, &symlink.link);
+                    if !self.exists(&target) && is_external_link(path, &symlink.link) {
+                        return Ok(SymlinkResolution::External(symlink.link.clone()));
+                    }
+                    self.follow_symlink(&target, visited, hops_left - 1)
+                }
+                _ => Ok(SymlinkResolution::Resolved(self.entry_at(idx))),
+            },
+            None => Ok(SymlinkResolution::Dangling(path.to_path_buf())),
+        }
+    }
+}
+
+impl<'a> SquashFSEntry<'a> {
+    /// Creates an entry from a backhand filesystem node and its inode number.
+    fn from_node(node: &'a Node<SquashfsFileReader>, inode: u32, block_size: u32) -> Self {
+        let size = match &node.inner {
+            InnerNode::File(file) => file_size(&file.basic, block_size),
+            _ => 0,
+        };
+
+        let kind = match &node.inner {
+            InnerNode::File(file) => EntryKind::File(&file.basic),
+            InnerNode::Dir(_) => EntryKind::Directory,
+            InnerNode::Symlink(symlink) => EntryKind::Symlink(symlink.link.clone()),
+            InnerNode::BlockDevice(device) => {
+                let (major, minor) = decode_device_number(device.device_number);
+                EntryKind::BlockDevice { major, minor }
+            }
+            InnerNode::CharacterDevice(device) => {
+                let (major, minor) = decode_device_number(device.device_number);
+                EntryKind::CharDevice { major, minor }
+            }
+            InnerNode::NamedPipe => EntryKind::Fifo,
+            InnerNode::Socket => EntryKind::Socket,
+        };
+
+        SquashFSEntry {
+            header: node.header,
+            path: node.fullpath.clone(),
+            size,
+            mode: node.header.permissions,
+            uid: node.header.uid,
+            gid: node.header.gid,
+            mtime: node.header.mtime,
+            inode,
+            kind,
+        }
+    }
+
+    /// Returns true if the entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        matches!(self.kind, EntryKind::File(_))
+    }
+
+    /// Returns true if the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, EntryKind::Directory)
+    }
+
+    /// Returns true if the entry is a regular file with sparse blocks, all-zero blocks
+    /// that aren't stored in the image. Such blocks are read back as zeros.
+    pub fn is_sparse(&self) -> bool {
+        match self.kind {
+            EntryKind::File(file) => file.block_sizes.iter().any(|block| block.size() == 0),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the entry is a symlink.
+    pub fn is_symlink(&self) -> bool {
+        matches!(self.kind, EntryKind::Symlink(_))
+    }
+
+    /// Formats the entry like a line of `ls -l`: mode string, uid, gid, size and path,
+    /// followed by ` -> target` for symlinks.
+    /// Device nodes show their major and minor numbers in place of the size.
+    pub fn format_long(&self) -> String {
+        let size = match self.kind {
+            EntryKind::BlockDevice { major, minor } | EntryKind::CharDevice { major, minor } => {
+                format!("{major}, {minor}")
+            }
+            _ => self.size.to_string(),
+        };
+        let mut line = format!(
+            "{} {} {} {:>8} {}",
+            self.mode_string(),
+            self.uid,
+            self.gid,
+            size,
+            self.path.display()
+        );
+        if let EntryKind::Symlink(target) = &self.kind {
+            line.push_str(" -> ");
+            line.push_str(&target.to_string_lossy());
+        }
+        line
+    }
+
+    /// Renders the entry type and permission bits like `ls -l`, e.g. `drwxr-xr-x`.
+    fn mode_string(&self) -> String {
+        let kind = match self.kind {
+            EntryKind::File(_) => '-',
+            EntryKind::Directory => 'd',
+            EntryKind::Symlink(_) => 'l',
+            EntryKind::BlockDevice { .. } => 'b',
+            EntryKind::CharDevice { .. } => 'c',
+            EntryKind::Fifo => 'p',
+            EntryKind::Socket => 's',
+            EntryKind::Unknown => '?',
+        };
+        let bit = |mask: u16, c: char| if self.mode & mask != 0 { c } else { '-' };
+        // setuid, setgid and sticky replace the matching execute bit
+        let special = |mask: u16, exec: u16, set: char| match (
+            self.mode & mask != 0,
+            self.mode & exec != 0,
+        ) {
+            (true, true) => set,
+            (true, false) => set.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        };
+
+        [
+            kind,
+            bit(0o400, 'r'),
+            bit(0o200, 'w'),
+            special(0o4000, 0o100, 's'),
+            bit(0o040, 'r'),
+            bit(0o020, 'w'),
+            special(0o2000, 0o010, 's'),
+            bit(0o004, 'r'),
+            bit(0o002, 'w'),
+            special(0o1000, 0o001, 't'),
+        ]
+        .iter()
+        .collect()
+    }
+}
+
+impl PartialEq for SquashFSEntry<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SquashFSEntry<'_> {}
+
+impl PartialOrd for SquashFSEntry<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SquashFSEntry<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path
+            .cmp(&other.path)
+            .then_with(|| self.kind.rank().cmp(&other.kind.rank()))
+    }
+}
+
+impl fmt::Display for SquashFSEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+/// Compares two paths component by component, ignoring ASCII case and the root.
+fn eq_ignore_ascii_case(a: &Path, b: &Path) -> bool {
+    let names = |path: &Path| {
+        path.components()
+            .filter(|component| !matches!(component, Component::RootDir | Component::CurDir))
+            .map(|component| component.as_os_str().as_bytes().to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+    names(a) == names(b)
+}
+
+/// Resolves a symlink target against the directory containing the symlink,
+/// returning an absolute path within the SquashFS filesystem.
+///
+/// # Arguments
+/// * `symlink` - The full path of the symlink itself.
+/// * `link` - The target stored in the symlink, absolute or relative.
+fn resolve_link_target(symlink: &Path, link: &Path) -> PathBuf {
+    let parent = symlink.parent().unwrap_or(Path::new("/"));
+    normalize_path(&Path::new("/").join(parent).join(link))
+}
+
+/// Returns true if a symlink target points outside the image: it is absolute, meant
+/// for the host when missing from the image, or it goes above the root.
+///
+/// # Arguments
+/// * `symlink` - The full path of the symlink itself.
+/// * `link` - The target stored in the symlink, absolute or relative.
+fn is_external_link(symlink: &Path, link: &Path) -> bool {
+    if link.has_root() {
+        return true;
+    }
+
+    let parent = symlink.parent().unwrap_or(Path::new("/"));
+    let mut depth = normalize_path(&Path::new("/").join(parent))
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count();
+    for component in link.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Checks whether the build can decompress data with the compressor of the given
+/// superblock id, as enabled by the `gzip`, `lzo`, `xz` and `zstd` features.
+/// backhand supports neither legacy LZMA (2) nor LZ4 (5).
+fn is_compressor_enabled(compressor: u16) -> bool {
+    let enabled = [
+        (1, cfg!(feature = "gzip")),
+        (3, cfg!(feature = "lzo")),
+        (4, cfg!(feature = "xz")),
+        (6, cfg!(feature = "zstd")),
+    ];
+    enabled.contains(&(compressor, true))
+}
+
+/// Lexically normalizes a path, collapsing `.`, `..` and redundant separators.
+/// `..` above the root stays at the root. Symlinks are not followed.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) => {}
+                _ => normalized.push(".."),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Returns, for each node, the index just past the last node under it, which is the
+/// next one for anything but a non-empty directory. Nodes are sorted by path, so
+/// everything under a directory directly follows it.
+fn subtree_ends(nodes: &[Node<SquashfsFileReader>]) -> Vec<usize> {
+    let mut ends = vec![nodes.len(); nodes.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        while let Some(&parent) = open.last() {
+            if node.fullpath.starts_with(&nodes[parent].fullpath) {
+                break;
+            }
+            ends[parent] = idx;
+            open.pop();
+        }
+        open.push(idx);
+    }
+    ends
+}
+
+/// Splits a device number, as stored in the inode using the Linux
+/// `new_encode_dev` layout, into its major and minor numbers.
+fn decode_device_number(device_number: u32) -> (u32, u32) {
+    let major = (device_number & 0xfff00) >> 8;
+    let minor = (device_number & 0xff) | ((device_number >> 12) & 0xfff00);
+    (major, minor)
+}
+
+/// Returns the full size of a file, or an error if it is over 4 GiB.
+///
+/// backhand truncates the size of such files to 32 bits and stops reading them
+/// there, so their contents would silently come back short.
+fn check_file_size(file: &BasicFile, block_size: u32) -> Result<u64> {
+    let size = file_size(file, block_size);
+    if size > u64::from(u32::MAX) {
+        return Err(SquishyError::FileTooLarge(size));
+    }
+    Ok(size)
+}
+
+/// Returns the full size of a file.
+///
+/// backhand stores extended file inodes as a `BasicFile`, truncating their size to
+/// 32 bits. The real size is recovered from the block count, since it must lie
+/// within a single block of the size implied by the blocks (and fragment).
+fn file_size(file: &BasicFile, block_size: u32) -> u64 {
+    let truncated = u64::from(file.file_size);
+    let blocks = file.block_sizes.len() as u64;
+    let block_size = u64::from(block_size);
+    let has_fragment = file.frag_index != 0xffffffff;
+
+    let lower_bound = match (blocks, has_fragment) {
+        (0, _) => return truncated,
+        (_, true) => blocks * block_size,
+        (_, false) => (blocks - 1) * block_size + 1,
+    };
+    lower_bound + (truncated.wrapping_sub(lower_bound) & u64::from(u32::MAX))
+}
+
+#[cfg(all(test, feature = "gzip"))]
+mod tests {
+    use backhand::DataSize;
+
+    use super::*;
+    use crate::testutil::{
+        big_contents, build_image, hardlink_image, header, sample_image, BLOCK_SIZE,
+    };
+
+    fn paths<'s>(entries: impl IntoIterator<Item = SquashFSEntry<'s>>) -> Vec<PathBuf> {
+        entries.into_iter().map(|entry| entry.path).collect()
+    }
+
+    /// Marks the data block at `index` of the file at `path` as sparse, as backhand
+    /// can't write sparse files. It must be the last block, or the blocks after it
+    /// would be read from the wrong place.
+    fn mark_sparse(squashfs: &mut SquashFS, path: &str, index: usize) {
+        let idx = squashfs.node_index(Path::new(path)).unwrap();
+        let InnerNode::File(file) = &mut squashfs.reader.root.nodes[idx].inner else {
+            panic!("{path} is not a file");
+        };
+        file.basic.block_sizes[index] = DataSize::new(0, false);
+    }
+
+    #[test]
+    fn from_path_missing_file_is_an_error() {
+        let result = SquashFS::from_path("/nonexistent/image.sqfs");
+        assert!(matches!(result, Err(SquishyError::Io(_))));
+    }
+
+    #[test]
+    fn from_path_without_image_is_an_error() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), b"not a squashfs image").unwrap();
+        let result = SquashFS::from_path(file.path());
+        assert!(matches!(result, Err(SquishyError::NoSquashFsFound)));
+    }
+
+    #[test]
+    fn finds_image_at_odd_offset_behind_fake_magic() {
+        let mut data = b"runtime hsqs not a superblock, ".to_vec();
+        assert_eq!(data.len() % 2, 1);
+        let offset = data.len() as u64;
+        data.extend(sample_image());
+
+        assert_eq!(SquashFS::probe(&mut Cursor::new(&data)), Some(offset));
+        let squashfs = SquashFS::from_bytes(&data).unwrap();
+        assert_eq!(squashfs.read_file("/usr/bin/a").unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn scan_limit_gives_up_before_a_later_image() {
+        let mut data = vec![0; 4096];
+        data.extend(sample_image());
+        let open =
+            |max_scan| SquashFS::new_with_scan_limit(BufReader::new(Cursor::new(&data)), max_scan);
+
+        assert!(matches!(open(1024), Err(SquishyError::NoSquashFsFound)));
+        let squashfs = open(8192).unwrap();
+        assert_eq!(squashfs.read_file("/usr/bin/a").unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn scan_start_hint_falls_back_to_a_full_scan() {
+        // A full scan finds the first image, so the second is only found from the hint.
+        let mut data = hardlink_image();
+        let second = data.len() as u64;
+        data.extend(sample_image());
+        let open = |builder: SquashFSBuilder| {
+            builder
+                .open_reader(BufReader::new(Cursor::new(data.as_slice())))
+                .unwrap()
+        };
+
+        let hinted = open(SquashFS::builder().scan_start(second));
+        assert!(hinted.get_entry("/usr/bin/a").is_some());
+
+        let past_the_end = data.len() as u64 + 10;
+        for start in [second + 1, past_the_end] {
+            let fallback = open(SquashFS::builder().scan_start(start));
+            assert!(fallback.get_entry("/other").is_some(), "{start}");
+        }
+    }
+
+    /// Returns a 64-bit little-endian ELF header and a single program header, with
+    /// the segment covering `payload` appended after them.
+    fn elf_stub(payload: &[u8]) -> Vec<u8> {
+        let mut elf = vec![0; 64 + 56];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4..7].copy_from_slice(&[2, 1, 1]);
+        elf[32..40].copy_from_slice(&64_u64.to_le_bytes());
+        elf[54..56].copy_from_slice(&56_u16.to_le_bytes());
+        elf[56..58].copy_from_slice(&1_u16.to_le_bytes());
+        elf[58..60].copy_from_slice(&64_u16.to_le_bytes());
+        let size = (elf.len() + payload.len()) as u64;
+        elf[64 + 32..64 + 40].copy_from_slice(&size.to_le_bytes());
+        elf.extend_from_slice(payload);
+        elf
+    }
+
+    #[test]
+    fn scan_starts_at_the_end_of_an_elf_stub() {
+        // An image within the ELF itself must not be picked over the appended one.
+        let mut data = elf_stub(&hardlink_image());
+        let offset = data.len() as u64;
+        data.extend(sample_image());
+
+        assert_eq!(elf_end(&mut Cursor::new(&data), 0).unwrap(), Some(offset));
+        assert_eq!(SquashFS::probe(&mut Cursor::new(&data)), Some(offset));
+        let squashfs = SquashFS::from_bytes(&data).unwrap();
+        assert_eq!(squashfs.read_file("/usr/bin/a").unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn truncated_elf_header_has_no_end() {
+        let elf = elf_stub(b"");
+        for len in [3, 40, 60] {
+            let mut reader = Cursor::new(&elf[..len]);
+            assert_eq!(elf_end(&mut reader, 0).unwrap(), None, "{len}");
+        }
+    }
+
+    #[test]
+    fn resolves_relative_symlinks() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let resolve = |path: &str| {
+            let entry = squashfs.get_entry(path).unwrap();
+            squashfs
+                .resolve_symlink(&entry)
+                .unwrap()
+                .map(|target| target.path)
+        };
+
+        assert_eq!(resolve("/usr/bin/rel"), Some(PathBuf::from("/usr/bin/big")));
+        assert_eq!(resolve("/usr/lib/up"), Some(PathBuf::from("/usr/bin/a")));
+        assert_eq!(
+            resolve("/usr/lib/libfoo.so.1"),
+            Some(PathBuf::from("/usr/lib/libfoo.so"))
+        );
+        assert_eq!(resolve("/abs"), Some(PathBuf::from("/usr/bin/a")));
+        assert_eq!(resolve("/dangling"), None);
+    }
+
+    #[test]
+    fn symlink_chain_over_hop_limit_is_an_error() {
+        let image = build_image(|fs| {
+            for i in 0..50 {
+                fs.push_symlink(format!("l{}", i + 1), format!("/l{i}"), header(0o777))
+                    .unwrap();
+            }
+            fs.push_file(Cursor::new(b"end".to_vec()), "/l50", header(0o644))
+                .unwrap();
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let start = squashfs.get_entry("/l0").unwrap();
+
+        assert!(matches!(
+            squashfs.resolve_symlink(&start),
+            Err(SquishyError::SymlinkError(_))
+        ));
+        assert!(squashfs.resolve_symlink_with_limit(&start, 49).is_err());
+        let target = squashfs.resolve_symlink_with_limit(&start, 50).unwrap();
+        assert_eq!(
+            target.map(|target| target.path),
+            Some(PathBuf::from("/l50"))
+        );
+    }
+
+    #[test]
+    fn symlink_cycle_is_an_error() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let entry = squashfs.get_entry("/loop_a").unwrap();
+
+        assert!(matches!(
+            squashfs.resolve_symlink(&entry),
+            Err(SquishyError::SymlinkError(_))
+        ));
+        assert!(squashfs.check_symlink(&entry).is_err());
+        assert!(squashfs.symlink_chain(&entry).is_err());
+    }
+
+    #[test]
+    fn check_symlink_tells_resolutions_apart() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let check = |path: &str| {
+            let entry = squashfs.get_entry(path).unwrap();
+            squashfs.check_symlink(&entry).unwrap()
+        };
+
+        assert_eq!(
+            check("/dangling"),
+            SymlinkResolution::Dangling(PathBuf::from("/missing"))
+        );
+        assert_eq!(
+            check("/external"),
+            SymlinkResolution::External(PathBuf::from("/etc/resolv.conf"))
+        );
+        assert_eq!(check("/usr/bin/a"), SymlinkResolution::NotSymlink);
+        match check("/abs") {
+            SymlinkResolution::Resolved(target) => assert_eq!(target.path, Path::new("/usr/bin/a")),
+            other => panic!("/abs resolved to {other:?}"),
+        }
+    }
+
+    #[test]
+    fn normalizes_lookup_paths() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        for path in [
+            "/usr/bin/a",
+            "usr/bin/a",
+            "/usr//bin/a",
+            "/usr/./bin/a",
+            "/usr/lib/../bin/a",
+            "/../usr/bin/a",
+        ] {
+            let entry = squashfs.get_entry(path);
+            assert_eq!(
+                entry.map(|entry| entry.path),
+                Some(PathBuf::from("/usr/bin/a")),
+                "{path}"
+            );
+            assert_eq!(squashfs.read_file(path).unwrap(), b"hello\n", "{path}");
+        }
+        assert!(!squashfs.exists("/usr/bin/../../a"));
+    }
+
+    #[test]
+    fn read_dir_lists_the_root() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let names: Vec<_> = squashfs
+            .read_dir("/")
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        let expected = [
+            "/abs",
+            "/binlink",
+            "/dangling",
+            "/external",
+            "/loop_a",
+            "/loop_b",
+            "/usr",
+        ];
+        assert_eq!(names, expected.map(PathBuf::from));
+        assert!(matches!(
+            squashfs.read_dir("/usr/bin/a"),
+            Err(SquishyError::NotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn entries_under_is_component_wise() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let expected = ["/usr/bin", "/usr/bin/a", "/usr/bin/big", "/usr/bin/rel"];
+        assert_eq!(
+            paths(squashfs.entries_under("/usr/bin")),
+            expected.map(PathBuf::from)
+        );
+        assert_eq!(squashfs.entries_under("/usr/bi").count(), 0);
+    }
+
+    #[test]
+    fn find_entries_takes_a_stateful_predicate() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let mut calls = 0;
+        let found = paths(squashfs.find_entries(|path| {
+            calls += 1;
+            path.starts_with("/usr/lib")
+        }));
+        assert_eq!(
+            found,
+            [
+                "/usr/lib",
+                "/usr/lib/libfoo.a",
+                "/usr/lib/libfoo.so",
+                "/usr/lib/libfoo.so.1",
+                "/usr/lib/up",
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(calls, squashfs.count_entries());
+    }
+
+    #[test]
+    fn counts_entries_by_kind() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        assert_eq!(squashfs.count_entries(), 23);
+        assert_eq!(
+            squashfs.count_by_kind(),
+            EntryCounts {
+                files: 6,
+                directories: 8,
+                symlinks: 9,
+                ..EntryCounts::default()
+            }
+        );
+    }
+
+    #[test]
+    fn walk_stops_after_the_first_match() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let mut visited = Vec::new();
+        squashfs.walk(|entry| {
+            visited.push(entry.path.clone());
+            if entry.is_file() {
+                WalkAction::Stop
+            } else {
+                WalkAction::Continue
+            }
+        });
+        assert_eq!(visited.len(), 10);
+        assert_eq!(visited.last().unwrap(), Path::new("/usr/bin/a"));
+    }
+
+    #[test]
+    fn looks_up_paths_ignoring_ascii_case() {
+        let image = build_image(|fs| {
+            fs.push_dir("/usr", header(0o755)).unwrap();
+            fs.push_dir("/usr/bin", header(0o755)).unwrap();
+            for path in ["/usr/bin/foo", "/usr/bin/FOO"] {
+                fs.push_file(Cursor::new(path), path, header(0o755))
+                    .unwrap();
+            }
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let entry = squashfs.get_entry_ci("USR/BIN/FOO").unwrap();
+        assert_eq!(entry.path, Path::new("/usr/bin/FOO"));
+        assert_eq!(
+            paths(squashfs.find_entries_ci("/Usr/Bin/Foo")),
+            [PathBuf::from("/usr/bin/FOO"), PathBuf::from("/usr/bin/foo")]
+        );
+        assert!(squashfs.get_entry_ci("/usr/bin/fo").is_none());
+    }
+
+    #[test]
+    fn find_first_returns_the_first_match_in_path_order() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let entry = squashfs
+            .find_first(|path| path.extension().is_some_and(|ext| ext == "so"))
+            .unwrap();
+        assert_eq!(entry.path, Path::new("/usr/lib/libfoo.so"));
+        assert!(squashfs
+            .find_first(|path| path.ends_with("missing"))
+            .is_none());
+    }
+
+    #[test]
+    fn entries_of_kind_counts_symlinks() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let symlinks = squashfs.entries_of_kind(EntryKindFilter::AnySymlink);
+        assert_eq!(symlinks.count(), 9);
+        assert!(squashfs
+            .entries_of_kind(EntryKindFilter::AnySymlink)
+            .all(|entry| entry.is_symlink()));
+    }
+
+    #[test]
+    fn reads_small_files_packed_into_a_fragment() {
+        let contents: Vec<Vec<u8>> = (1..=4_u8).map(|i| vec![i; 100 * usize::from(i)]).collect();
+        let files = contents.clone();
+        let image = build_image(|fs| {
+            for (i, contents) in files.into_iter().enumerate() {
+                fs.push_file(Cursor::new(contents), format!("/small{i}"), header(0o644))
+                    .unwrap();
+            }
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        for (i, expected) in contents.iter().enumerate() {
+            let path = format!("/small{i}");
+            let layout = squashfs.file_layout(&path).unwrap();
+            assert_eq!(
+                (layout.blocks, layout.fragment_index),
+                (0, Some(0)),
+                "{path}"
+            );
+            assert_eq!(&squashfs.read_file(&path).unwrap(), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn reads_multi_block_files() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        assert_eq!(squashfs.read_file("/usr/bin/big").unwrap(), big_contents());
+        assert_eq!(squashfs.stat("/usr/bin/big").unwrap().size, 3 * 4096 + 100);
+    }
+
+    #[test]
+    fn open_file_reads_sparse_blocks_as_zeros() {
+        let mut contents = vec![b'a'; BLOCK_SIZE as usize];
+        contents.resize(2 * BLOCK_SIZE as usize, 0);
+        let data = contents.clone();
+        let image = build_image(|fs| {
+            fs.push_file(Cursor::new(data), "/hole", header(0o644))
+                .unwrap()
+        });
+        let mut squashfs = SquashFS::from_bytes(&image).unwrap();
+        mark_sparse(&mut squashfs, "/hole", 1);
+        assert!(squashfs.stat("/hole").unwrap().is_sparse());
+
+        let mut read = Vec::new();
+        squashfs
+            .open_file("/hole")
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert_eq!(read, contents);
+        assert_eq!(squashfs.read_file("/hole").unwrap(), contents);
+    }
+
+    #[test]
+    fn write_file_reads_sparse_blocks_of_a_copied_file_as_zeros() {
+        let mut contents = vec![b'a'; BLOCK_SIZE as usize];
+        contents.resize(2 * BLOCK_SIZE as usize, 0);
+        let data = contents.clone();
+        let image = build_image(|fs| {
+            fs.push_file(Cursor::new(data), "/hole", header(0o644))
+                .unwrap()
+        });
+        let mut squashfs = SquashFS::from_bytes(&image).unwrap();
+        mark_sparse(&mut squashfs, "/hole", 1);
+        let EntryKind::File(file) = squashfs.stat("/hole").unwrap().kind else {
+            panic!("/hole is not a file");
+        };
+        let file = file.clone();
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("hole");
+        squashfs.write_file(&file, &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), contents);
+    }
+
+    #[test]
+    fn read_file_rejects_what_is_not_a_file() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        for path in ["/usr/bin", "/abs"] {
+            assert!(
+                matches!(squashfs.read_file(path), Err(SquishyError::NotAFile(p)) if p == Path::new(path)),
+                "{path}"
+            );
+        }
+        assert!(matches!(
+            squashfs.read_file("/usr/bin/missing"),
+            Err(SquishyError::FileNotFound(p)) if p == Path::new("/usr/bin/missing")
+        ));
+    }
+
+    #[test]
+    fn read_file_to_string_requires_utf8() {
+        let image = build_image(|fs| {
+            fs.push_file(Cursor::new("héllo\n"), "/text", header(0o644))
+                .unwrap();
+            fs.push_file(
+                Cursor::new(vec![b'a', 0xff, 0xfe]),
+                "/binary",
+                header(0o644),
+            )
+            .unwrap();
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        assert_eq!(squashfs.read_file_to_string("/text").unwrap(), "héllo\n");
+        assert!(matches!(
+            squashfs.read_file_to_string("/binary"),
+            Err(SquishyError::InvalidUtf8(p)) if p == Path::new("/binary")
+        ));
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn hash_file_computes_the_sha256_digest() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let hex = |path: &str| {
+            let digest = squashfs.hash_file(path).unwrap();
+            digest
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        };
+
+        assert_eq!(
+            hex("/usr/bin/a"),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+        assert_eq!(
+            hex("/usr/bin/big"),
+            "27aff3c267b17a34c9f2a77a44060eb5a2f1c0ad669931720ed82516a7451260"
+        );
+    }
+
+    #[test]
+    fn read_file_chunks_ends_with_a_short_chunk() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let contents = big_contents();
+
+        let chunks: Vec<_> = squashfs
+            .read_file_chunks("/usr/bin/big", 5000)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let sizes: Vec<_> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [5000, 5000, contents.len() - 10_000]);
+        assert_eq!(chunks.concat(), contents);
+
+        let chunks = squashfs.read_file_chunks("/usr/bin/a", 0).unwrap();
+        let sizes: Vec<_> = chunks.map(|chunk| chunk.unwrap().len()).collect();
+        assert_eq!(sizes, [1; 6]);
+    }
+
+    #[test]
+    fn read_file_range_reads_the_window() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let contents = big_contents();
+        let block = BLOCK_SIZE as usize;
+
+        for (offset, len) in [
+            (0, 10),
+            (block - 5, 10),
+            (2 * block + 1, block),
+            (0, 1 << 20),
+        ] {
+            let end = (offset + len).min(contents.len());
+            assert_eq!(
+                squashfs
+                    .read_file_range("/usr/bin/big", offset as u64, len)
+                    .unwrap(),
+                &contents[offset..end],
+                "{offset}+{len}"
+            );
+        }
+        let past_end = squashfs.read_file_range("/usr/bin/big", 1 << 20, 10);
+        assert!(past_end.unwrap().is_empty());
+    }
+
+    /// Returns a file with the given size, truncated as backhand does, and data blocks.
+    fn file_with_size(size: u64, blocks: usize, fragment: bool) -> BasicFile {
+        BasicFile {
+            blocks_start: 96,
+            frag_index: if fragment { 0 } else { 0xffffffff },
+            block_offset: 0,
+            file_size: size as u32,
+            block_sizes: vec![DataSize::new(1024, false); blocks],
+        }
+    }
+
+    #[test]
+    fn entry_kind_names_parse_back_into_filters() {
+        let file = file_with_size(100, 0, true);
+        let kinds = [
+            (EntryKind::File(&file), EntryKindFilter::File),
+            (EntryKind::Directory, EntryKindFilter::Directory),
+            (
+                EntryKind::BlockDevice { major: 8, minor: 1 },
+                EntryKindFilter::BlockDevice,
+            ),
+            (
+                EntryKind::CharDevice { major: 1, minor: 3 },
+                EntryKindFilter::CharDevice,
+            ),
+            (EntryKind::Fifo, EntryKindFilter::Fifo),
+            (EntryKind::Socket, EntryKindFilter::Socket),
+        ];
+        for (kind, filter) in kinds {
+            let name = kind.to_string();
+            assert_eq!(name.parse::<EntryKindFilter>().unwrap(), filter, "{name}");
+            assert_eq!(filter.to_string(), name);
+            assert!(filter.matches(&kind), "{name}");
+        }
+        assert!(matches!(
+            EntryKind::Unknown.to_string().parse::<EntryKindFilter>(),
+            Err(SquishyError::UnknownEntryKind(name)) if name == "unknown"
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn reports_zstd_compression() {
+        let image = build_image(|fs| {
+            fs.set_compressor(backhand::FilesystemCompressor::new(Compressor::Zstd, None).unwrap());
+            fs.push_file(Cursor::new(b"zstd".to_vec()), "/file", header(0o644))
+                .unwrap();
+        });
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        assert_eq!(squashfs.compression(), Compression::Zstd);
+        assert_eq!(squashfs.read_file("/file").unwrap(), b"zstd");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn entries_serialize_to_json() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let json = |path: &str| {
+            let entry = squashfs.get_entry(path).unwrap();
+            (serde_json::to_value(&entry).unwrap(), entry.inode)
+        };
+
+        let (file, inode) = json("/usr/bin/a");
+        assert_eq!(
+            file,
+            serde_json::json!({
+                "path": "/usr/bin/a",
+                "size": 6,
+                "mode": 0o755,
+                "uid": 1000,
+                "gid": 1000,
+                "mtime": crate::testutil::MTIME,
+                "inode": inode,
+                "kind": {"type": "file"},
+            })
+        );
+        let (symlink, _) = json("/usr/bin/rel");
+        assert_eq!(
+            symlink["kind"],
+            serde_json::json!({"type": "symlink", "target": "big"})
+        );
+    }
+
+    #[test]
+    fn file_size_recovers_sizes_over_4_gib() {
+        const GIB: u64 = 1 << 30;
+        let block_size = 128 * 1024;
+        let blocks = |size: u64| size.div_ceil(u64::from(block_size)) as usize;
+
+        let cases = [
+            (100, 0, true),
+            (3 * u64::from(block_size), 3, false),
+            (3 * u64::from(block_size) + 100, 3, true),
+            (u64::from(u32::MAX), blocks(u64::from(u32::MAX)), false),
+            (4 * GIB, blocks(4 * GIB), false),
+            (5 * GIB, blocks(5 * GIB), false),
+            (5 * GIB + 1, blocks(5 * GIB + 1), false),
+            (5 * GIB + 100, blocks(5 * GIB), true),
+        ];
+        for (size, blocks, fragment) in cases {
+            let file = file_with_size(size, blocks, fragment);
+            assert_eq!(file_size(&file, block_size), size, "{size}");
+        }
+    }
+
+    #[test]
+    fn files_over_4_gib_cannot_be_read() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let size = 5 << 30;
+        let file = file_with_size(size, (size / u64::from(BLOCK_SIZE)) as usize, false);
+
+        assert!(matches!(
+            squashfs.file_reader(&file),
+            Err(SquishyError::FileTooLarge(s)) if s == size
+        ));
+        assert!(matches!(
+            squashfs.copy_file(&file, &mut std::io::sink()),
+            Err(SquishyError::FileTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn read_through_symlink_follows_every_component() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        assert_eq!(
+            squashfs.read_through_symlink("/binlink/rel").unwrap(),
+            big_contents()
+        );
+        assert_eq!(
+            squashfs.read_through_symlink("/usr/lib/up").unwrap(),
+            b"hello\n"
+        );
+        assert_eq!(
+            squashfs.read_through_symlink("usr/bin/a").unwrap(),
+            b"hello\n"
+        );
+
+        assert!(matches!(
+            squashfs.read_through_symlink("/binlink"),
+            Err(SquishyError::NotAFile(path)) if path == Path::new("/usr/bin")
+        ));
+        for path in ["/dangling", "/external", "/loop_a"] {
+            assert!(
+                matches!(
+                    squashfs.read_through_symlink(path),
+                    Err(SquishyError::SymlinkError(_))
+                ),
+                "{path}"
+            );
+        }
+        assert!(matches!(
+            squashfs.read_through_symlink("/binlink/missing"),
+            Err(SquishyError::FileNotFound(_))
+        ));
+    }
+
+    #[cfg(feature = "glob")]
+    #[test]
+    fn find_glob_matches_versioned_shared_libraries() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let expected = ["/usr/lib/libfoo.so", "/usr/lib/libfoo.so.1"];
+        assert_eq!(
+            paths(squashfs.find_glob("**/*.so*").unwrap()),
+            expected.map(PathBuf::from)
+        );
+        assert_eq!(squashfs.find_glob("*.so*").unwrap().count(), 0);
+    }
+
+    #[cfg(feature = "glob")]
+    #[test]
+    fn find_glob_wildcards_stay_within_a_component() {
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+
+        let expected = [
+            "/usr/bin/a",
+            "/usr/bin/big",
+            "/usr/bin/rel",
+            "/usr/binaries/x",
+        ];
+        assert_eq!(
+            paths(squashfs.find_glob("usr/b*/*").unwrap()),
+            expected.map(PathBuf::from)
+        );
+        let expected = ["/usr/bin", "/usr/bin/big", "/usr/binaries"];
+        assert_eq!(
+            paths(squashfs.find_glob("usr/**/b*").unwrap()),
+            expected.map(PathBuf::from)
+        );
+        let expected = ["/usr/share/icons/hicolor"];
+        assert_eq!(
+            paths(squashfs.find_glob("usr/share/icons/*").unwrap()),
+            expected.map(PathBuf::from)
+        );
+        assert_eq!(
+            squashfs.find_glob("/usr/share/icons/**").unwrap().count(),
+            2
+        );
+    }
+
+    #[test]
+    fn hardlinks_share_their_inode_number() {
+        let image = hardlink_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let inode = |path: &str| squashfs.get_entry(path).unwrap().inode;
+
+        assert_eq!(inode("/hardlink_a"), inode("/hardlink_b"));
+        assert_ne!(inode("/hardlink_a"), inode("/other"));
+        assert_eq!(squashfs.read_file("/hardlink_b").unwrap(), b"linked");
+
+        let image = sample_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let inodes: HashSet<_> = squashfs.entries().map(|entry| entry.inode).collect();
+        assert_eq!(inodes.len(), squashfs.count_entries());
+    }
+
+    #[test]
+    fn reads_files_by_inode() {
+        let image = hardlink_image();
+        let squashfs = SquashFS::from_bytes(&image).unwrap();
+        let inode = |path: &str| squashfs.get_entry(path).unwrap().inode;
+
+        assert_eq!(
+            squashfs.read_file_by_inode(inode("/hardlink_b")).unwrap(),
+            b"linked"
+        );
+        assert!(matches!(
+            squashfs.read_file_by_inode(inode("/")),
+            Err(SquishyError::NotAFile(path)) if path == Path::new("/")
+        ));
+        let unused = squashfs.entries().map(|entry| entry.inode).max().unwrap() + 1;
+        assert!(matches!(
+            squashfs.read_file_by_inode(unused),
+            Err(SquishyError::InodeNotFound(inode)) if inode == unused
+        ));
+    }
+
+    #[test]
+    fn detects_big_endian_flavours() {
+        for (target, kind) in [
+            ("be_v4_0", ImageKind::BigEndian),
+            ("avm_be_v4_0", ImageKind::AvmBigEndian),
+        ] {
+            let image = build_image(|fs| {
+                fs.set_kind(Kind::from_target(target).unwrap());
+                fs.push_file(Cursor::new(b"big".to_vec()), "/file", header(0o644))
+                    .unwrap();
+            });
+            assert_eq!(&image[..4], b"sqsh");
+
+            let squashfs = SquashFS::from_bytes(&image).unwrap();
+            assert_eq!(squashfs.kind(), kind, "{target}");
+            assert_eq!(squashfs.read_file("/file").unwrap(), b"big", "{target}");
         }
     }
+
+    #[test]
+    fn disabled_codec_is_unsupported_compression() {
+        let mut image = sample_image();
+        // The compressor id is the u16 at offset 20 of the superblock, 5 is LZ4.
+        image[20..22].copy_from_slice(&5u16.to_le_bytes());
+
+        let result = SquashFS::from_bytes(&image);
+        assert!(matches!(
+            result,
+            Err(SquishyError::UnsupportedCompression(5))
+        ));
+    }
 }
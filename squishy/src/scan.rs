@@ -0,0 +1,387 @@
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+use crate::Result;
+
+/// Size of the chunks read while scanning for the SquashFS magic bytes.
+const SCAN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of the on-disk SquashFS v4 superblock.
+const SUPERBLOCK_SIZE: usize = 96;
+
+/// Largest amount of data held by a metadata block, compressed or not.
+pub(crate) const METADATA_SIZE: usize = 8192;
+
+/// Flag set in the header of a metadata block stored uncompressed.
+pub(crate) const METADATA_UNCOMPRESSED: u16 = 1 << 15;
+
+/// Magic bytes of a little-endian SquashFS superblock.
+const MAGIC_LE: [u8; 4] = *b"hsqs";
+
+/// Magic bytes of a big-endian SquashFS superblock.
+const MAGIC_BE: [u8; 4] = *b"sqsh";
+
+/// Byte order of a SquashFS superblock, as given by its magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Returns the byte order for the given magic bytes, if they are SquashFS magic bytes.
+    fn from_magic(magic: &[u8]) -> Option<Self> {
+        if magic == MAGIC_LE {
+            Some(Endian::Little)
+        } else if magic == MAGIC_BE {
+            Some(Endian::Big)
+        } else {
+            None
+        }
+    }
+}
+
+/// Incremental scanner looking for the little and big-endian SquashFS magic
+/// bytes at every byte offset.
+pub(crate) struct MagicScanner {
+    buf: Vec<u8>,
+    /// Offset in the reader of `buf[0]`
+    position: u64,
+    /// Index in `buf` to resume searching from
+    start: usize,
+    /// Number of valid bytes in `buf`
+    filled: usize,
+    /// Offset in the reader at which to stop looking for magic bytes
+    end: Option<u64>,
+}
+
+impl MagicScanner {
+    /// Creates a scanner starting at the reader's current position.
+    pub(crate) fn new<R: Seek>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            buf: vec![0_u8; SCAN_CHUNK_SIZE],
+            position: reader.stream_position()?,
+            start: 0,
+            filled: 0,
+            end: None,
+        })
+    }
+
+    /// Limits the scan to magic bytes starting within `max_bytes` of the start position.
+    pub(crate) fn with_limit(mut self, max_bytes: u64) -> Self {
+        self.end = Some(self.position.saturating_add(max_bytes));
+        self
+    }
+
+    /// Returns the offset of the next occurrence of either magic bytes, along with
+    /// the byte order they indicate, or None once the end of the reader is reached.
+    ///
+    /// The reader must be left at the position it was at after the previous call.
+    pub(crate) fn next_offset<R: Read>(&mut self, reader: &mut R) -> Result<Option<(u64, Endian)>> {
+        loop {
+            if let Some((idx, endian)) = self.buf[self.start..self.filled]
+                .windows(MAGIC_LE.len())
+                .enumerate()
+                .find_map(|(idx, w)| Endian::from_magic(w).map(|endian| (idx, endian)))
+            {
+                let found = self.start + idx;
+                self.start = found + 1;
+                let offset = self.position + found as u64;
+                if self.end.is_some_and(|end| offset >= end) {
+                    return Ok(None);
+                }
+                return Ok(Some((offset, endian)));
+            }
+            if self
+                .end
+                .is_some_and(|end| self.position + self.filled as u64 >= end)
+            {
+                return Ok(None);
+            }
+
+            // Keep the tail so a magic split across two reads is still found
+            let carried = (self.filled - self.start).min(MAGIC_LE.len() - 1);
+            self.buf.copy_within(self.filled - carried..self.filled, 0);
+            self.position += (self.filled - carried) as u64;
+            self.start = 0;
+            self.filled = carried;
+
+            let read = match reader.read(&mut self.buf[self.filled..]) {
+                Ok(0) => return Ok(None),
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            self.filled += read;
+        }
+    }
+}
+
+/// Checks whether the bytes at `offset` look like a real SquashFS v4 superblock
+/// rather than the magic bytes happening to appear in other data.
+///
+/// The reader position is restored before returning.
+pub(crate) fn is_valid_superblock<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<bool> {
+    let mut superblock = [0_u8; SUPERBLOCK_SIZE];
+    if !read_at(reader, offset, &mut superblock)? {
+        return Ok(false);
+    }
+    Ok(Endian::from_magic(&superblock[..4])
+        .is_some_and(|endian| validate_superblock(&superblock, endian)))
+}
+
+/// Returns the byte order given by the magic bytes at `offset`, or None if
+/// there are no SquashFS magic bytes there.
+///
+/// The reader position is restored before returning.
+pub(crate) fn magic_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Option<Endian>> {
+    let mut magic = [0_u8; 4];
+    if !read_at(reader, offset, &mut magic)? {
+        return Ok(None);
+    }
+    Ok(Endian::from_magic(&magic))
+}
+
+/// Magic bytes at the start of an ELF file.
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+
+/// Section type of sections that take no space in the file, such as `.bss`.
+const SHT_NOBITS: u32 = 8;
+
+/// Returns the offset at which an ELF file starting at `offset` ends, i.e. the end of
+/// its headers, header tables, segments and sections, whichever is furthest. Data
+/// appended to the ELF, such as the SquashFS image of an AppImage, starts from there.
+///
+/// Returns None if there is no ELF header at `offset` or its tables cannot be read.
+/// The reader position is restored before returning.
+pub(crate) fn elf_end<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Option<u64>> {
+    let mut header = [0_u8; 64];
+    if !read_at(reader, offset, &mut header[..52])? || header[..4] != ELF_MAGIC {
+        return Ok(None);
+    }
+    let is_64 = match header[4] {
+        1 => false,
+        2 => true,
+        _ => return Ok(None),
+    };
+    let endian = match header[5] {
+        1 => Endian::Little,
+        2 => Endian::Big,
+        _ => return Ok(None),
+    };
+    if is_64 && !read_at(reader, offset, &mut header)? {
+        return Ok(None);
+    }
+
+    let uint = |bytes: &[u8], at: usize, size: usize| {
+        let mut value = [0_u8; 8];
+        match endian {
+            Endian::Little => {
+                value[..size].copy_from_slice(&bytes[at..at + size]);
+                u64::from_le_bytes(value)
+            }
+            Endian::Big => {
+                value[8 - size..].copy_from_slice(&bytes[at..at + size]);
+                u64::from_be_bytes(value)
+            }
+        }
+    };
+    // Offsets of (phoff, shoff, phentsize, phnum, shentsize, shnum), and the size
+    // of addresses and offsets, for each class
+    let (phoff, shoff, phentsize, phnum, shentsize, shnum, word) = if is_64 {
+        (32, 40, 54, 56, 58, 60, 8)
+    } else {
+        (28, 32, 42, 44, 46, 48, 4)
+    };
+    let ehsize = if is_64 { 64 } else { 52 };
+    let phoff = uint(&header, phoff, word);
+    let shoff = uint(&header, shoff, word);
+    let phentsize = uint(&header, phentsize, 2);
+    let phnum = uint(&header, phnum, 2);
+    let shentsize = uint(&header, shentsize, 2);
+    let shnum = uint(&header, shnum, 2);
+
+    // Where (offset, size) of segments and sections are within their table entries
+    let (p_offset, p_filesz) = if is_64 { (8, 32) } else { (4, 16) };
+    let (sh_offset, sh_size) = if is_64 { (24, 32) } else { (16, 20) };
+    if (phnum != 0 && phentsize < p_filesz as u64 + word as u64)
+        || (shnum != 0 && shentsize < sh_size as u64 + word as u64)
+    {
+        return Ok(None);
+    }
+
+    let mut end = ehsize
+        .max(phoff.saturating_add(phentsize * phnum))
+        .max(shoff.saturating_add(shentsize * shnum));
+    let mut entry = vec![0_u8; phentsize.max(shentsize) as usize];
+    for i in 0..phnum {
+        let entry = &mut entry[..phentsize as usize];
+        if !read_at(
+            reader,
+            offset.saturating_add(phoff.saturating_add(i * phentsize)),
+            entry,
+        )? {
+            return Ok(None);
+        }
+        end = end.max(uint(entry, p_offset, word).saturating_add(uint(entry, p_filesz, word)));
+    }
+    for i in 0..shnum {
+        let entry = &mut entry[..shentsize as usize];
+        if !read_at(
+            reader,
+            offset.saturating_add(shoff.saturating_add(i * shentsize)),
+            entry,
+        )? {
+            return Ok(None);
+        }
+        if uint(entry, 4, 4) as u32 != SHT_NOBITS {
+            end = end.max(uint(entry, sh_offset, word).saturating_add(uint(entry, sh_size, word)));
+        }
+    }
+    Ok(Some(offset.saturating_add(end)))
+}
+
+/// Returns the compressor id recorded in the superblock at `offset`, read in the
+/// given byte order, or None if the reader ends first.
+///
+/// The reader position is restored before returning.
+pub(crate) fn compressor_at<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    endian: Endian,
+) -> Result<Option<u16>> {
+    let mut compressor = [0_u8; 2];
+    if !read_at(reader, offset + 20, &mut compressor)? {
+        return Ok(None);
+    }
+    Ok(Some(match endian {
+        Endian::Little => u16::from_le_bytes(compressor),
+        Endian::Big => u16::from_be_bytes(compressor),
+    }))
+}
+
+/// Offsets of the tables the entries are read from, as recorded in a SquashFS v4
+/// superblock. They are relative to the start of the image.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Tables {
+    pub(crate) inode_table: u64,
+    pub(crate) dir_table: u64,
+}
+
+/// Returns the table offsets recorded in the superblock at `offset`, read in the
+/// given byte order, or None if the reader ends first.
+///
+/// The reader position is restored before returning.
+pub(crate) fn tables_at<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    endian: Endian,
+) -> Result<Option<Tables>> {
+    let mut superblock = [0_u8; SUPERBLOCK_SIZE];
+    if !read_at(reader, offset, &mut superblock)? {
+        return Ok(None);
+    }
+    let u64_at = |at: usize| {
+        let bytes = superblock[at..at + 8].try_into().unwrap();
+        match endian {
+            Endian::Little => u64::from_le_bytes(bytes),
+            Endian::Big => u64::from_be_bytes(bytes),
+        }
+    };
+    Ok(Some(Tables {
+        inode_table: u64_at(64),
+        dir_table: u64_at(72),
+    }))
+}
+
+/// Returns the raw, still compressed, inode table of the image at `offset`, or None
+/// if the table offsets are inverted or past the end of the reader.
+///
+/// The reader position is restored before returning.
+pub(crate) fn read_inode_table<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    tables: Tables,
+) -> Result<Option<Vec<u8>>> {
+    let position = reader.stream_position()?;
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+
+    let (Some(start), Some(end)) = (
+        offset.checked_add(tables.inode_table),
+        offset.checked_add(tables.dir_table),
+    ) else {
+        return Ok(None);
+    };
+    if start > end || end > len {
+        return Ok(None);
+    }
+    let mut table = vec![0_u8; (end - start) as usize];
+    Ok(read_at(reader, start, &mut table)?.then_some(table))
+}
+
+/// Checks whether the metadata blocks of a table, read with their headers in the
+/// given byte order, follow each other up to exactly its end. Headers read in the
+/// wrong byte order give lengths that all but never do.
+pub(crate) fn metadata_blocks_fit(table: &[u8], endian: Endian) -> bool {
+    let mut at = 0;
+    while let Some(header) = table.get(at..at + 2) {
+        let header = [header[0], header[1]];
+        let header = match endian {
+            Endian::Little => u16::from_le_bytes(header),
+            Endian::Big => u16::from_be_bytes(header),
+        };
+        let len = usize::from(header & !METADATA_UNCOMPRESSED);
+        if len == 0 || len > METADATA_SIZE {
+            return false;
+        }
+        at += 2 + len;
+    }
+    at == table.len()
+}
+
+/// Fills `buf` with the bytes at `offset`, returning false if the reader ends first.
+///
+/// The reader position is restored before returning.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, buf: &mut [u8]) -> Result<bool> {
+    let position = reader.stream_position()?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let read = reader.read_exact(buf);
+    reader.seek(SeekFrom::Start(position))?;
+
+    match read {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Sanity checks the fields of a SquashFS v4 superblock in the given byte order.
+fn validate_superblock(superblock: &[u8; SUPERBLOCK_SIZE], endian: Endian) -> bool {
+    let u16_at = |at: usize| {
+        let bytes = [superblock[at], superblock[at + 1]];
+        match endian {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        }
+    };
+    let u32_at = |at: usize| {
+        let bytes = superblock[at..at + 4].try_into().unwrap();
+        match endian {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        }
+    };
+
+    let block_size = u32_at(12);
+    let compressor = u16_at(20);
+    let block_log = u16_at(22);
+    let version_major = u16_at(28);
+    let version_minor = u16_at(30);
+
+    version_major == 4
+        && version_minor == 0
+        && (1..=6).contains(&compressor)
+        && block_size.is_power_of_two()
+        && (4096..=1024 * 1024).contains(&block_size)
+        && u32::from(block_log) == block_size.trailing_zeros()
+}
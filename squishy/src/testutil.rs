@@ -0,0 +1,158 @@
+//! SquashFS images built in memory for the unit tests.
+
+use std::io::Cursor;
+
+use backhand::{
+    compression::{CompressionAction, Compressor, DefaultCompressor},
+    kind::Kind,
+    BackhandError, FilesystemCompressor, FilesystemWriter, NodeHeader,
+};
+
+/// Block size of the test images, the smallest SquashFS allows, so files span
+/// several blocks without being large.
+pub const BLOCK_SIZE: u32 = 4096;
+
+/// Modification time of every entry in the test images.
+pub const MTIME: u32 = 1_700_000_000;
+
+/// Builds a gzip-compressed image holding the entries pushed by `build`.
+pub fn build_image<F>(build: F) -> Vec<u8>
+where
+    F: FnOnce(&mut FilesystemWriter<'_, '_, 'static>),
+{
+    let mut writer = FilesystemWriter::default();
+    writer.set_compressor(FilesystemCompressor::new(Compressor::Gzip, None).unwrap());
+    writer.set_block_size(BLOCK_SIZE);
+    writer.set_time(MTIME);
+    writer.set_root_mode(0o755);
+    writer.set_no_padding();
+    build(&mut writer);
+
+    let mut image = Cursor::new(Vec::new());
+    writer.write(&mut image).unwrap();
+    image.into_inner()
+}
+
+/// Returns a header with the given mode and the owner and mtime of the test images.
+pub fn header(mode: u16) -> NodeHeader {
+    NodeHeader::new(mode, 1000, 1000, MTIME)
+}
+
+/// Contents of `/usr/bin/big`: three full blocks followed by a tail in a fragment.
+pub fn big_contents() -> Vec<u8> {
+    (0..3 * BLOCK_SIZE as usize + 100)
+        .map(|i| (i % 251) as u8)
+        .collect()
+}
+
+/// Builds the image most tests run against:
+///
+/// ```text
+/// /abs -> /usr/bin/a
+/// /binlink -> usr/bin
+/// /dangling -> missing
+/// /external -> /etc/resolv.conf
+/// /loop_a -> loop_b
+/// /loop_b -> loop_a
+/// /usr/bin/a                        "hello\n", 0755
+/// /usr/bin/big                      big_contents(), 0644
+/// /usr/bin/rel -> big
+/// /usr/binaries/x                   "x"
+/// /usr/lib/libfoo.a                 "archive"
+/// /usr/lib/libfoo.so                "shared"
+/// /usr/lib/libfoo.so.1 -> libfoo.so
+/// /usr/lib/up -> ../bin/a
+/// /usr/share/icons/hicolor/app.png  "png"
+/// ```
+pub fn sample_image() -> Vec<u8> {
+    build_image(|fs| {
+        for dir in [
+            "/usr",
+            "/usr/bin",
+            "/usr/binaries",
+            "/usr/lib",
+            "/usr/share",
+            "/usr/share/icons",
+            "/usr/share/icons/hicolor",
+        ] {
+            fs.push_dir(dir, header(0o755)).unwrap();
+        }
+
+        let files: [(&str, Vec<u8>, u16); 6] = [
+            ("/usr/bin/a", b"hello\n".to_vec(), 0o755),
+            ("/usr/bin/big", big_contents(), 0o644),
+            ("/usr/binaries/x", b"x".to_vec(), 0o644),
+            ("/usr/lib/libfoo.a", b"archive".to_vec(), 0o644),
+            ("/usr/lib/libfoo.so", b"shared".to_vec(), 0o755),
+            ("/usr/share/icons/hicolor/app.png", b"png".to_vec(), 0o644),
+        ];
+        for (path, contents, mode) in files {
+            fs.push_file(Cursor::new(contents), path, header(mode))
+                .unwrap();
+        }
+
+        let symlinks = [
+            ("/abs", "/usr/bin/a"),
+            ("/binlink", "usr/bin"),
+            ("/dangling", "missing"),
+            ("/external", "/etc/resolv.conf"),
+            ("/loop_a", "loop_b"),
+            ("/loop_b", "loop_a"),
+            ("/usr/bin/rel", "big"),
+            ("/usr/lib/libfoo.so.1", "libfoo.so"),
+            ("/usr/lib/up", "../bin/a"),
+        ];
+        for (path, link) in symlinks {
+            fs.push_symlink(link, path, header(0o777)).unwrap();
+        }
+    })
+}
+
+/// Compressor whose output is always larger than its input, so the writer stores
+/// every block uncompressed and the image can be patched byte for byte.
+struct Stored;
+
+impl CompressionAction for Stored {
+    fn decompress(
+        &self,
+        bytes: &[u8],
+        out: &mut Vec<u8>,
+        compressor: Compressor,
+    ) -> Result<(), BackhandError> {
+        DefaultCompressor.decompress(bytes, out, compressor)
+    }
+
+    fn compress(
+        &self,
+        bytes: &[u8],
+        _: FilesystemCompressor,
+        _: u32,
+    ) -> Result<Vec<u8>, BackhandError> {
+        Ok(vec![0; bytes.len() + 1])
+    }
+}
+
+/// Builds an image where `/hardlink_a` and `/hardlink_b` are the same inode, a file
+/// holding "linked", next to `/other`, a file of its own with the same contents.
+///
+/// backhand cannot write hardlinks, so the directory entry of `/hardlink_b` is
+/// patched to point at the inode of `/hardlink_a`.
+pub fn hardlink_image() -> Vec<u8> {
+    let mut image = build_image(|fs| {
+        fs.set_kind(Kind::new(&Stored));
+        for path in ["/hardlink_a", "/hardlink_b", "/other"] {
+            fs.push_file(Cursor::new(b"linked".to_vec()), path, header(0o644))
+                .unwrap();
+        }
+    });
+
+    // A directory entry is the offset of its inode in its metadata block, its inode
+    // number relative to the others of the run, its type and the size of its name,
+    // followed by the name.
+    let entry =
+        |image: &[u8], name: &[u8]| image.windows(name.len()).position(|w| w == name).unwrap() - 8;
+    let a = entry(&image, b"hardlink_a");
+    let b = entry(&image, b"hardlink_b");
+    image.copy_within(a..a + 4, b);
+    image
+}